@@ -0,0 +1,439 @@
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::ParasiteError;
+
+// Half-width of the resampling kernel, in input samples at the cutoff
+const SINC_TAPS: usize = 32;
+
+// Decoded audio held as interleaved f32 samples in -1.0..1.0
+#[derive(Clone)]
+pub struct AudioBuffer {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub samples: Vec<f32>,
+}
+
+impl AudioBuffer {
+    pub fn read_wav(path: &Path) -> Result<AudioBuffer> {
+        let mut reader = hound::WavReader::open(path)
+            .map_err(|e| ParasiteError::AudioProcessing(format!("Cannot read {:?}: {}", path, e)))?;
+        let spec = reader.spec();
+
+        let samples = match spec.sample_format {
+            hound::SampleFormat::Float => reader
+                .samples::<f32>()
+                .collect::<Result<Vec<_>, _>>()?,
+            hound::SampleFormat::Int => {
+                let full_scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|s| s.map(|s| s as f32 / full_scale))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+
+        Ok(AudioBuffer {
+            sample_rate: spec.sample_rate,
+            channels: spec.channels,
+            samples,
+        })
+    }
+
+    // Read only the frames between `start` and `end` of a (possibly long) WAV
+    pub fn read_wav_range(path: &Path, start: Duration, end: Duration) -> Result<AudioBuffer> {
+        let mut reader = hound::WavReader::open(path)
+            .map_err(|e| ParasiteError::AudioProcessing(format!("Cannot read {:?}: {}", path, e)))?;
+        let spec = reader.spec();
+
+        let total = reader.duration();
+        let first = ((start.as_secs_f64() * spec.sample_rate as f64) as u32).min(total);
+        let last = ((end.as_secs_f64() * spec.sample_rate as f64).ceil() as u32).min(total);
+        reader.seek(first)?;
+        let count = (last.saturating_sub(first)) as usize * spec.channels as usize;
+
+        let samples = match spec.sample_format {
+            hound::SampleFormat::Float => reader
+                .samples::<f32>()
+                .take(count)
+                .collect::<Result<Vec<_>, _>>()?,
+            hound::SampleFormat::Int => {
+                let full_scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .take(count)
+                    .map(|s| s.map(|s| s as f32 / full_scale))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+
+        Ok(AudioBuffer {
+            sample_rate: spec.sample_rate,
+            channels: spec.channels,
+            samples,
+        })
+    }
+
+    // Write as integer PCM (16/24/32 bit) or 32-bit float when `float` is set
+    pub fn write_wav(&self, path: &Path, bits_per_sample: u16, float: bool) -> Result<()> {
+        let spec = hound::WavSpec {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            bits_per_sample: if float { 32 } else { bits_per_sample },
+            sample_format: if float { hound::SampleFormat::Float } else { hound::SampleFormat::Int },
+        };
+        let mut writer = hound::WavWriter::create(path, spec)?;
+
+        if float {
+            for &s in &self.samples {
+                writer.write_sample(s)?;
+            }
+        } else {
+            let full_scale = ((1i64 << (bits_per_sample - 1)) - 1) as f64;
+            for &s in &self.samples {
+                let value = (s.clamp(-1.0, 1.0) as f64 * full_scale).round() as i32;
+                writer.write_sample(value)?;
+            }
+        }
+
+        writer.finalize()?;
+        Ok(())
+    }
+
+    // Write as big-endian integer PCM AIFF, inserting `extra_chunks` (id, data)
+    // ahead of the sound data (e.g. an APPL chunk for device-specific metadata)
+    pub fn write_aiff(&self, path: &Path, bits_per_sample: u16, extra_chunks: &[([u8; 4], Vec<u8>)]) -> Result<()> {
+        let bytes_per_sample = (bits_per_sample as usize).div_ceil(8);
+        let full_scale = ((1i64 << (bits_per_sample - 1)) - 1) as f64;
+
+        let mut comm = Vec::new();
+        comm.extend_from_slice(&(self.channels as i16).to_be_bytes());
+        comm.extend_from_slice(&(self.frames() as u32).to_be_bytes());
+        comm.extend_from_slice(&(bits_per_sample as i16).to_be_bytes());
+        comm.extend_from_slice(&extended_float(self.sample_rate as f64));
+
+        let mut ssnd = Vec::with_capacity(8 + self.samples.len() * bytes_per_sample);
+        ssnd.extend_from_slice(&0u32.to_be_bytes()); // Offset
+        ssnd.extend_from_slice(&0u32.to_be_bytes()); // Block size
+        for &s in &self.samples {
+            // Left-justify the sample within its bytes, as AIFF requires
+            let value = (s.clamp(-1.0, 1.0) as f64 * full_scale).round() as i32;
+            let value = value << (bytes_per_sample * 8 - bits_per_sample as usize);
+            ssnd.extend_from_slice(&value.to_be_bytes()[4 - bytes_per_sample..]);
+        }
+
+        let mut body = b"AIFF".to_vec();
+        let mut push_chunk = |id: &[u8; 4], data: &[u8]| {
+            body.extend_from_slice(id);
+            body.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            body.extend_from_slice(data);
+            if data.len() % 2 == 1 {
+                body.push(0);
+            }
+        };
+        push_chunk(b"COMM", &comm);
+        for (id, data) in extra_chunks {
+            push_chunk(id, data);
+        }
+        push_chunk(b"SSND", &ssnd);
+
+        let mut output = b"FORM".to_vec();
+        output.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        output.extend_from_slice(&body);
+        std::fs::write(path, output)?;
+        Ok(())
+    }
+
+    pub fn frames(&self) -> usize {
+        if self.channels == 0 { 0 } else { self.samples.len() / self.channels as usize }
+    }
+
+    pub fn peak(&self) -> f32 {
+        self.samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()))
+    }
+
+    // Scale so the loudest sample hits `target` (linear, 0.0-1.0)
+    pub fn normalize_peak(&mut self, target: f32) {
+        let peak = self.peak();
+        if peak > 0.0 {
+            let gain = target / peak;
+            self.samples.iter_mut().for_each(|s| *s *= gain);
+        }
+    }
+
+    // Add triangular (TPDF) dither of one least significant bit at `bits`,
+    // ready for quantizing. The noise is seeded so output is reproducible.
+    pub fn dither(&mut self, bits: u16) {
+        let lsb = 1.0 / ((1i64 << (bits - 1)) - 1) as f32;
+        let mut state: u32 = 0x9E37_79B9;
+        let mut uniform = || {
+            // xorshift32
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as f32 / u32::MAX as f32
+        };
+        for s in &mut self.samples {
+            *s += (uniform() - uniform()) * lsb;
+        }
+    }
+
+    // Append another buffer of the same rate and channel count
+    pub fn append(&mut self, other: &AudioBuffer) {
+        self.samples.extend_from_slice(&other.samples);
+    }
+
+    // Append `frames` frames of silence
+    pub fn append_silence(&mut self, frames: usize) {
+        let len = self.samples.len() + frames * self.channels as usize;
+        self.samples.resize(len, 0.0);
+    }
+
+    // Average all channels down to one
+    pub fn to_mono(&self) -> AudioBuffer {
+        if self.channels <= 1 {
+            return self.clone();
+        }
+
+        let channels = self.channels as usize;
+        let samples = self.samples
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+
+        AudioBuffer { sample_rate: self.sample_rate, channels: 1, samples }
+    }
+
+    // Duplicate a mono signal across `channels`
+    pub fn with_channels(&self, channels: u16) -> AudioBuffer {
+        if channels == self.channels {
+            return self.clone();
+        }
+
+        let mono = self.to_mono();
+        let samples = mono.samples
+            .iter()
+            .flat_map(|&s| std::iter::repeat_n(s, channels as usize))
+            .collect();
+
+        AudioBuffer { sample_rate: self.sample_rate, channels, samples }
+    }
+
+    // Windowed-sinc (Blackman) sample rate conversion
+    pub fn resample(&self, sample_rate: u32) -> AudioBuffer {
+        if sample_rate == self.sample_rate || self.frames() == 0 {
+            return AudioBuffer { sample_rate, ..self.clone() };
+        }
+
+        let channels = self.channels as usize;
+        let in_frames = self.frames();
+        let ratio = sample_rate as f64 / self.sample_rate as f64;
+        let out_frames = (in_frames as f64 * ratio).round() as usize;
+
+        // Lower the cutoff when downsampling to avoid aliasing
+        let cutoff = ratio.min(1.0);
+        let half_width = (SINC_TAPS as f64 / cutoff).ceil() as i64;
+
+        let mut samples = vec![0.0f32; out_frames * channels];
+        for out in 0..out_frames {
+            let position = out as f64 / ratio;
+            let centre = position.floor() as i64;
+
+            let mut acc = vec![0.0f64; channels];
+            let mut weight_sum = 0.0;
+            for k in (centre - half_width + 1)..=(centre + half_width) {
+                if k < 0 || k >= in_frames as i64 {
+                    continue;
+                }
+                let x = position - k as f64;
+                let weight = cutoff * sinc(cutoff * x) * blackman(x, half_width as f64);
+                weight_sum += weight;
+                for (c, a) in acc.iter_mut().enumerate() {
+                    *a += self.samples[k as usize * channels + c] as f64 * weight;
+                }
+            }
+
+            // Normalise so DC passes at unity gain near the edges
+            let norm = if weight_sum.abs() > 1e-9 { 1.0 / weight_sum } else { 0.0 };
+            for (c, a) in acc.iter().enumerate() {
+                samples[out * channels + c] = (*a * norm) as f32;
+            }
+        }
+
+        AudioBuffer { sample_rate, channels: self.channels, samples }
+    }
+}
+
+// Bit depth of a WAV and whether it holds float samples, so processed audio
+// can be written back in the same format
+pub fn wav_format(path: &Path) -> Result<(u16, bool)> {
+    let reader = hound::WavReader::open(path)
+        .map_err(|e| ParasiteError::AudioProcessing(format!("Cannot read {:?}: {}", path, e)))?;
+    let spec = reader.spec();
+    Ok((spec.bits_per_sample, spec.sample_format == hound::SampleFormat::Float))
+}
+
+// Second-order IIR section (transposed direct form II). Coefficients are
+// normalised so a0 is 1; the constructors follow the RBJ audio EQ cookbook.
+pub struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    pub fn new(b: [f64; 3], a: [f64; 2]) -> Biquad {
+        Biquad { b, a, z: [0.0; 2] }
+    }
+
+    pub fn high_pass(sample_rate: u32, frequency: f64, q: f64) -> Biquad {
+        let (cos, alpha) = Biquad::angle(sample_rate, frequency, q);
+        Biquad::normalized(
+            [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    pub fn peaking(sample_rate: u32, frequency: f64, gain_db: f64, q: f64) -> Biquad {
+        let (cos, alpha) = Biquad::angle(sample_rate, frequency, q);
+        let a = 10f64.powf(gain_db / 40.0);
+        Biquad::normalized(
+            [1.0 + alpha * a, -2.0 * cos, 1.0 - alpha * a],
+            [1.0 + alpha / a, -2.0 * cos, 1.0 - alpha / a],
+        )
+    }
+
+    pub fn low_shelf(sample_rate: u32, frequency: f64, gain_db: f64) -> Biquad {
+        let (cos, alpha) = Biquad::angle(sample_rate, frequency, FRAC_1_SQRT_2);
+        let a = 10f64.powf(gain_db / 40.0);
+        let k = 2.0 * a.sqrt() * alpha;
+        Biquad::normalized(
+            [
+                a * ((a + 1.0) - (a - 1.0) * cos + k),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                a * ((a + 1.0) - (a - 1.0) * cos - k),
+            ],
+            [(a + 1.0) + (a - 1.0) * cos + k, -2.0 * ((a - 1.0) + (a + 1.0) * cos), (a + 1.0) + (a - 1.0) * cos - k],
+        )
+    }
+
+    pub fn high_shelf(sample_rate: u32, frequency: f64, gain_db: f64) -> Biquad {
+        let (cos, alpha) = Biquad::angle(sample_rate, frequency, FRAC_1_SQRT_2);
+        let a = 10f64.powf(gain_db / 40.0);
+        let k = 2.0 * a.sqrt() * alpha;
+        Biquad::normalized(
+            [
+                a * ((a + 1.0) + (a - 1.0) * cos + k),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                a * ((a + 1.0) + (a - 1.0) * cos - k),
+            ],
+            [(a + 1.0) - (a - 1.0) * cos + k, 2.0 * ((a - 1.0) - (a + 1.0) * cos), (a + 1.0) - (a - 1.0) * cos - k],
+        )
+    }
+
+    pub fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+
+    // Cosine of the centre frequency and the cookbook's alpha, with the
+    // frequency kept below Nyquist
+    fn angle(sample_rate: u32, frequency: f64, q: f64) -> (f64, f64) {
+        let frequency = frequency.clamp(1.0, sample_rate as f64 * 0.49);
+        let w0 = 2.0 * PI * frequency / sample_rate as f64;
+        (w0.cos(), w0.sin() / (2.0 * q))
+    }
+
+    fn normalized(b: [f64; 3], a: [f64; 3]) -> Biquad {
+        Biquad::new([b[0] / a[0], b[1] / a[0], b[2] / a[0]], [a[1] / a[0], a[2] / a[0]])
+    }
+}
+
+// 80-bit IEEE 754 extended precision, as used for the AIFF sample rate
+fn extended_float(value: f64) -> [u8; 10] {
+    let mut bytes = [0u8; 10];
+    if value <= 0.0 {
+        return bytes;
+    }
+
+    let exponent = value.log2().floor() as i32;
+    let mantissa = (value / 2f64.powi(exponent) * (1u64 << 63) as f64) as u64;
+    bytes[0..2].copy_from_slice(&((exponent + 16383) as u16).to_be_bytes());
+    bytes[2..10].copy_from_slice(&mantissa.to_be_bytes());
+    bytes
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-9 { 1.0 } else { (PI * x).sin() / (PI * x) }
+}
+
+fn blackman(x: f64, half_width: f64) -> f64 {
+    if x.abs() >= half_width {
+        return 0.0;
+    }
+    let n = (x + half_width) / (2.0 * half_width);
+    0.42 - 0.5 * (2.0 * PI * n).cos() + 0.08 * (4.0 * PI * n).cos()
+}
+
+// Magnitude spectrum of a Hann-windowed frame, zero-padded to a power of two.
+// Returns the bins from DC up to (but not including) Nyquist.
+pub fn magnitude_spectrum(frame: &[f32]) -> Vec<f32> {
+    let size = frame.len().next_power_of_two().max(2);
+    let mut re = vec![0.0f64; size];
+    let mut im = vec![0.0f64; size];
+    for (i, &s) in frame.iter().enumerate() {
+        let window = 0.5 - 0.5 * (2.0 * PI * i as f64 / frame.len() as f64).cos();
+        re[i] = s as f64 * window;
+    }
+
+    fft(&mut re, &mut im);
+    re.iter()
+        .zip(&im)
+        .take(size / 2)
+        .map(|(r, i)| (r * r + i * i).sqrt() as f32)
+        .collect()
+}
+
+// In-place iterative radix-2 FFT; the length must be a power of two
+fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+
+    // Bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (w_im, w_re) = (angle * k as f64).sin_cos();
+                let a = start + k;
+                let b = a + len / 2;
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::ParasiteError;
+
+// Name of the per-project config file, looked up in the input directory
+pub const PROJECT_CONFIG: &str = "parasite.conf";
+
+// Settings read from `key = value` files. The global config
+// (`$XDG_CONFIG_HOME/parasite/config`, else `~/.config/parasite/config`) is
+// read first, then the project's `parasite.conf` overrides it. A `[section]`
+// header prefixes the keys that follow with `section.`; a `#` at the start of
+// a line or after whitespace, outside double quotes, starts a comment.
+#[derive(Default)]
+pub struct Config {
+    values: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn load(input_dir: &str) -> Result<Config> {
+        let mut config = Config::default();
+        if let Some(path) = global_config_path() {
+            config.merge_file(&path)?;
+        }
+        config.merge_file(&Path::new(input_dir).join(PROJECT_CONFIG))?;
+        Ok(config)
+    }
+
+    // Add a file's settings, overriding earlier ones. A missing file is fine.
+    fn merge_file(&mut self, path: &Path) -> Result<()> {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Ok(());
+        };
+
+        let mut section = String::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(ParasiteError::Config(format!("{}:{}: expected `key = value`", path.display(), i + 1)).into());
+            };
+            let key = if section.is_empty() {
+                key.trim().to_string()
+            } else {
+                format!("{}.{}", section, key.trim())
+            };
+            // Quotes around the whole value are dropped; quotes inside it are kept
+            let value = value.trim();
+            let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value).to_string();
+            self.values.insert(key, value);
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    // Names of the `[parent.<name>]` sections, sorted
+    pub fn subsections(&self, parent: &str) -> Vec<String> {
+        let prefix = format!("{}.", parent);
+        let mut names: Vec<String> = self.values
+            .keys()
+            .filter_map(|key| key.strip_prefix(&prefix)?.split_once('.').map(|(name, _)| name.to_string()))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    // A duration given in milliseconds
+    pub fn get_ms(&self, key: &str) -> Result<Option<Duration>> {
+        self.parse(key, |v| v.parse::<u64>().ok().map(Duration::from_millis))
+    }
+
+    pub fn get_bool(&self, key: &str) -> Result<Option<bool>> {
+        self.parse(key, |v| match v {
+            "true" | "yes" | "on" => Some(true),
+            "false" | "no" | "off" => Some(false),
+            _ => None,
+        })
+    }
+
+    pub fn get_f32(&self, key: &str) -> Result<Option<f32>> {
+        self.parse(key, |v| v.parse::<f32>().ok())
+    }
+
+    fn parse<T>(&self, key: &str, parse: impl Fn(&str) -> Option<T>) -> Result<Option<T>> {
+        match self.get(key) {
+            Some(value) => parse(value)
+                .map(Some)
+                .ok_or_else(|| ParasiteError::Config(format!("invalid value for {}: '{}'", key, value)).into()),
+            None => Ok(None),
+        }
+    }
+}
+
+// `line` up to its comment, if it has one. A `#` inside a quoted value or
+// within a word (`take#2`) is part of the value.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut previous = ' ';
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted && previous.is_whitespace() => return &line[..i],
+            _ => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+fn global_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("parasite").join("config"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_comments_but_not_hashes_in_values() {
+        assert_eq!(strip_comment("# a comment"), "");
+        assert_eq!(strip_comment("gap_ms = 20 # between slices"), "gap_ms = 20 ");
+        assert_eq!(strip_comment("namer = \"llm -s 'title #1'\" # quoted"), "namer = \"llm -s 'title #1'\" ");
+        assert_eq!(strip_comment("output = /music/take#2"), "output = /music/take#2");
+    }
+}
@@ -0,0 +1,179 @@
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use super::{xml_escape, KeyMap};
+use crate::ParasiteError;
+
+// A Drum Rack has 128 pads, one per MIDI note
+const MAX_PADS: usize = 128;
+
+// Write `<name>.adg`: a gzipped Drum Rack preset with one Simpler per pad.
+// A Simpler holds a single sample, so every zone gets its own pad, ascending
+// from the key map's lowest key. Samples are referenced relative to the .adg.
+// Refuses key maps with more zones than there are pads above the lowest key.
+pub fn write_drum_rack(keymap: &KeyMap, dir: &Path, name: &str) -> Result<PathBuf> {
+    let base_key = keymap.zones.iter().map(|z| z.lo_key).min().unwrap_or(36);
+    let pads_free = MAX_PADS - base_key as usize;
+    if keymap.zones.len() > pads_free {
+        return Err(ParasiteError::AudioProcessing(format!(
+            "{} samples need a pad each but a Drum Rack has {} pads from note {}; use the chromatic layout or a lower base key",
+            keymap.zones.len(),
+            pads_free,
+            base_key
+        )).into());
+    }
+
+    let mut pads = Vec::new();
+    for (i, zone) in keymap.zones.iter().enumerate() {
+        let note = base_key + i as u8;
+        let sample = &keymap.samples[zone.sample];
+        let reader = hound::WavReader::open(&sample.path)?;
+        let absolute = std::fs::canonicalize(&sample.path).unwrap_or_else(|_| sample.path.clone());
+
+        pads.push(Pad {
+            note,
+            label: sample.text.clone(),
+            file_name: sample.path.file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            absolute_path: absolute.to_string_lossy().to_string(),
+            frames: reader.duration(),
+            sample_rate: reader.spec().sample_rate,
+        });
+    }
+
+    let path = dir.join(format!("{}.adg", name));
+    let mut encoder = GzEncoder::new(File::create(&path)?, Compression::default());
+    encoder.write_all(render(&pads).as_bytes())?;
+    encoder.finish()?;
+    Ok(path)
+}
+
+struct Pad {
+    note: u8,
+    label: String,
+    file_name: String,
+    absolute_path: String,
+    frames: u32,
+    sample_rate: u32,
+}
+
+fn render(pads: &[Pad]) -> String {
+    let mut xml = String::new();
+    let _ = writeln!(xml, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    let _ = writeln!(xml, "<Ableton MajorVersion=\"5\" MinorVersion=\"11.0_433\" SchemaChangeCount=\"3\" Creator=\"parasite\" Revision=\"\">");
+    let _ = writeln!(xml, "\t<GroupDevicePreset>");
+    let _ = writeln!(xml, "\t\t<OverwriteProtectionNumber Value=\"2816\" />");
+    let _ = writeln!(xml, "\t\t<Device>");
+    let _ = writeln!(xml, "\t\t\t<DrumGroupDevice Id=\"0\">");
+    let _ = writeln!(xml, "\t\t\t\t<LomId Value=\"0\" />");
+    let _ = writeln!(xml, "\t\t\t\t<IsExpanded Value=\"true\" />");
+    let _ = writeln!(xml, "\t\t\t\t<On><Manual Value=\"true\" /></On>");
+    let _ = writeln!(xml, "\t\t\t\t<Branches />");
+    let _ = writeln!(xml, "\t\t\t</DrumGroupDevice>");
+    let _ = writeln!(xml, "\t\t</Device>");
+    let _ = writeln!(xml, "\t\t<PresetRef />");
+    let _ = writeln!(xml, "\t\t<BranchPresets>");
+
+    for (i, pad) in pads.iter().enumerate() {
+        render_pad(&mut xml, i, pad);
+    }
+
+    let _ = writeln!(xml, "\t\t</BranchPresets>");
+    let _ = writeln!(xml, "\t</GroupDevicePreset>");
+    let _ = writeln!(xml, "</Ableton>");
+    xml
+}
+
+fn render_pad(xml: &mut String, id: usize, pad: &Pad) {
+    let label = xml_escape(&pad.label);
+
+    let _ = writeln!(xml, "\t\t\t<DrumBranchPreset Id=\"{}\">", id);
+    let _ = writeln!(xml, "\t\t\t\t<Name Value=\"{}\" />", label);
+    let _ = writeln!(xml, "\t\t\t\t<IsSoloed Value=\"false\" />");
+    let _ = writeln!(xml, "\t\t\t\t<DevicePresets>");
+    let _ = writeln!(xml, "\t\t\t\t\t<AbletonDevicePreset Id=\"0\">");
+    let _ = writeln!(xml, "\t\t\t\t\t\t<OverwriteProtectionNumber Value=\"2816\" />");
+    let _ = writeln!(xml, "\t\t\t\t\t\t<Device>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t<OriginalSimpler Id=\"0\">");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t<LomId Value=\"0\" />");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t<UserName Value=\"{}\" />", label);
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t<On><Manual Value=\"true\" /></On>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t<Player>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t<MultiSampleMap>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t<SampleParts>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t<MultiSamplePart Id=\"0\" HasImportedSlicePoints=\"false\" NeedsAnalysisData=\"false\">");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t<LomId Value=\"0\" />");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t<Name Value=\"{}\" />", label);
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t<KeyRange><Min Value=\"0\" /><Max Value=\"127\" /><CrossfadeMin Value=\"0\" /><CrossfadeMax Value=\"127\" /></KeyRange>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t<VelocityRange><Min Value=\"1\" /><Max Value=\"127\" /><CrossfadeMin Value=\"1\" /><CrossfadeMax Value=\"127\" /></VelocityRange>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t<RootKey Value=\"60\" />");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t<SampleStart Value=\"0\" />");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t<SampleEnd Value=\"{}\" />", pad.frames);
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t<SampleRef>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t\t<FileRef>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t\t\t<RelativePathType Value=\"1\" />");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t\t\t<RelativePath Value=\"{}\" />", xml_escape(&pad.file_name));
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t\t\t<Path Value=\"{}\" />", xml_escape(&pad.absolute_path));
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t\t\t<Type Value=\"1\" />");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t\t</FileRef>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t\t<DefaultDuration Value=\"{}\" />", pad.frames);
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t\t<DefaultSampleRate Value=\"{}\" />", pad.sample_rate);
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t\t</SampleRef>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t\t</MultiSamplePart>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t\t</SampleParts>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t\t</MultiSampleMap>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t</Player>");
+    // Playback mode 1 = one-shot, as suits vocal hits
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t\t<Globals><PlaybackMode Value=\"1\" /></Globals>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t\t</OriginalSimpler>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t</Device>");
+    let _ = writeln!(xml, "\t\t\t\t\t\t<PresetRef />");
+    let _ = writeln!(xml, "\t\t\t\t\t\t<BranchDeviceId Value=\"device:ableton:midiinstrument:OriginalSimpler\" />");
+    let _ = writeln!(xml, "\t\t\t\t\t</AbletonDevicePreset>");
+    let _ = writeln!(xml, "\t\t\t\t</DevicePresets>");
+    let _ = writeln!(xml, "\t\t\t\t<MixerPreset />");
+    let _ = writeln!(xml, "\t\t\t\t<BranchSelectorRange><Min Value=\"0\" /><Max Value=\"0\" /><CrossfadeMin Value=\"0\" /><CrossfadeMax Value=\"0\" /></BranchSelectorRange>");
+    let _ = writeln!(xml, "\t\t\t\t<SessionViewBranchWidth Value=\"55\" />");
+    // Drum Rack stores the receiving note counted down from 128
+    let _ = writeln!(xml, "\t\t\t\t<ZoneSettings>");
+    let _ = writeln!(xml, "\t\t\t\t\t<ReceivingNote Value=\"{}\" />", 128 - pad.note as u16);
+    let _ = writeln!(xml, "\t\t\t\t\t<SendingNote Value=\"60\" />");
+    let _ = writeln!(xml, "\t\t\t\t\t<ChokeGroup Value=\"0\" />");
+    let _ = writeln!(xml, "\t\t\t\t</ZoneSettings>");
+    let _ = writeln!(xml, "\t\t\t</DrumBranchPreset>");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{ExportSample, KeyLayout};
+
+    fn sample(text: &str) -> ExportSample {
+        ExportSample {
+            path: PathBuf::from(format!("{}.wav", text)),
+            name: text.to_string(),
+            text: text.to_string(),
+            peak: 1.0,
+            source: None,
+            license: None,
+        }
+    }
+
+    #[test]
+    fn refuses_more_zones_than_pads_above_the_base_key() {
+        // 93 takes of one word on a single round-robin key from note 36: one too many
+        let samples = (0..93).map(|_| sample("again")).collect();
+        let keymap = KeyMap::build(samples, KeyLayout::RoundRobin, 36);
+        assert_eq!(keymap.zones.len(), 93);
+
+        let error = write_drum_rack(&keymap, &std::env::temp_dir(), "too_many").unwrap_err();
+        assert!(error.to_string().contains("92 pads from note 36"), "{}", error);
+    }
+}
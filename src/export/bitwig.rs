@@ -0,0 +1,77 @@
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use super::{xml_escape, KeyMap};
+
+// Write `<name>.multisample`: a zip holding multisample.xml and the samples
+pub fn write_multisample(keymap: &KeyMap, dir: &Path, name: &str) -> Result<PathBuf> {
+    let path = dir.join(format!("{}.multisample", name));
+    let mut zip = ZipWriter::new(File::create(&path)?);
+    let options = SimpleFileOptions::default();
+
+    zip.start_file("multisample.xml", options)?;
+    zip.write_all(render(keymap, name)?.as_bytes())?;
+
+    // WAVs barely compress, so store them as-is
+    let stored = options.compression_method(zip::CompressionMethod::Stored);
+    for sample in &keymap.samples {
+        zip.start_file(file_name(&sample.path), stored)?;
+        zip.write_all(&fs::read(&sample.path)?)?;
+    }
+
+    zip.finish()?;
+    Ok(path)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "sample.wav".to_string())
+}
+
+fn render(keymap: &KeyMap, name: &str) -> Result<String> {
+    let mut xml = String::new();
+    let _ = writeln!(xml, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    let _ = writeln!(xml, "<multisample name=\"{}\">", xml_escape(name));
+    let _ = writeln!(xml, "   <generator>parasite</generator>");
+    let _ = writeln!(xml, "   <category>Vocal</category>");
+    let _ = writeln!(xml, "   <creator>parasite</creator>");
+    let _ = writeln!(xml, "   <description>Vocal samples cut from transcripts</description>");
+    let _ = writeln!(xml, "   <keywords/>");
+
+    // One Bitwig group per key-map group, named after the word
+    for group in 0..keymap.group_count() {
+        if let Some(zone) = keymap.zones.iter().find(|z| z.group == group) {
+            let _ = writeln!(xml, "   <group name=\"{}\"/>", xml_escape(&keymap.samples[zone.sample].text));
+        }
+    }
+
+    for zone in &keymap.zones {
+        let sample = &keymap.samples[zone.sample];
+        let frames = hound::WavReader::open(&sample.path)?.duration();
+        let zone_logic = if zone.seq_length > 1 { "round-robin" } else { "always-play" };
+
+        let _ = writeln!(
+            xml,
+            "   <sample file=\"{}\" gain=\"0.00\" group=\"{}\" sample-start=\"0.000\" sample-stop=\"{}.000\" tune=\"0.0\" zone-logic=\"{}\">",
+            xml_escape(&file_name(&sample.path)), zone.group, frames, zone_logic
+        );
+        let _ = writeln!(
+            xml,
+            "      <key root=\"{}\" track=\"0.0000\" tune=\"0.00\" low=\"{}\" high=\"{}\"/>",
+            zone.root_key, zone.lo_key, zone.hi_key
+        );
+        let _ = writeln!(xml, "      <velocity low=\"{}\" high=\"{}\"/>", zone.lo_vel, zone.hi_vel);
+        let _ = writeln!(xml, "      <loop mode=\"off\" start=\"0.000\" stop=\"{}.000\"/>", frames);
+        let _ = writeln!(xml, "   </sample>");
+    }
+
+    let _ = writeln!(xml, "</multisample>");
+    Ok(xml)
+}
@@ -0,0 +1,167 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::audio::AudioBuffer;
+use crate::metadata;
+use crate::ParasiteError;
+
+use super::{elektron, DeviceFormat, KeyMap};
+
+// Peak level for normalized slices (-1 dBFS)
+const NORMALIZE_PEAK: f32 = 0.891;
+
+// The OP-1 drum sampler takes up to 24 slices in 12 seconds of 44.1kHz mono
+const OP1_MAX_SLICES: usize = 24;
+const OP1_MAX_SECONDS: f64 = 12.0;
+// OP-1 slice positions are frame offsets scaled to fill i32 over 12 seconds
+const OP1_POSITION_SCALE: u64 = 4058;
+
+const CHAIN_FORMAT: DeviceFormat = DeviceFormat { sample_rate: 44100, bits_per_sample: 16, max_channels: 2 };
+const OP1_FORMAT: DeviceFormat = DeviceFormat { sample_rate: 44100, bits_per_sample: 16, max_channels: 1 };
+
+pub struct ChainOptions {
+    pub gap: Duration,   // Silence inserted after each slice
+    pub normalize: bool, // Peak-normalize each slice before joining
+}
+
+struct Slice {
+    start: u32, // First frame of the slice
+    end: u32,   // Frame after the slice's last sample (excludes the gap)
+    label: String,
+}
+
+// Write `<name>_chain/<name>.wav` with one cue marker per slice and an
+// Octatrack `.ot` sidecar describing the same slices. The chain lives in its
+// own folder so it isn't picked up as a sample by later exports. Refuses
+// more slices than an .ot file holds so the two always agree.
+pub fn write_wav_chain(keymap: &KeyMap, dir: &Path, name: &str, options: &ChainOptions) -> Result<PathBuf> {
+    if keymap.zones.len() > elektron::OT_MAX_SLICES {
+        return Err(ParasiteError::AudioProcessing(format!(
+            "{} samples; an Octatrack chain holds at most {} slices",
+            keymap.zones.len(),
+            elektron::OT_MAX_SLICES
+        )).into());
+    }
+    let (buffer, slices) = build_chain(keymap, CHAIN_FORMAT, options)?;
+
+    let chain_dir = dir.join(format!("{}_chain", name));
+    std::fs::create_dir_all(&chain_dir)?;
+    let path = chain_dir.join(format!("{}.wav", name));
+    buffer.write_wav(&path, CHAIN_FORMAT.bits_per_sample, false)?;
+
+    let markers: Vec<(Duration, String)> = slices
+        .iter()
+        .map(|s| (frames_to_duration(s.start, buffer.sample_rate), s.label.clone()))
+        .collect();
+    metadata::write_wav_cues(&path, &markers)?;
+
+    let ot_slices: Vec<(u32, u32)> = slices.iter().map(|s| (s.start, s.end)).collect();
+    elektron::write_ot(&path.with_extension("ot"), buffer.sample_rate, buffer.frames() as u32, &ot_slices)?;
+
+    Ok(path)
+}
+
+// Write `<name>.aif` as an OP-1 drum kit, slice points stored in the APPL chunk
+pub fn write_op1_chain(keymap: &KeyMap, dir: &Path, name: &str, options: &ChainOptions) -> Result<PathBuf> {
+    if keymap.zones.len() > OP1_MAX_SLICES {
+        return Err(ParasiteError::AudioProcessing(format!(
+            "{} samples; the OP-1 drum sampler holds at most {} slices",
+            keymap.zones.len(),
+            OP1_MAX_SLICES
+        )).into());
+    }
+    let (buffer, slices) = build_chain(keymap, OP1_FORMAT, options)?;
+
+    let seconds = buffer.frames() as f64 / buffer.sample_rate as f64;
+    if seconds > OP1_MAX_SECONDS {
+        return Err(ParasiteError::AudioProcessing(format!(
+            "Chain is {:.1}s; the OP-1 drum sampler holds at most {:.0}s",
+            seconds, OP1_MAX_SECONDS
+        )).into());
+    }
+
+    let mut appl = b"op-1".to_vec();
+    appl.extend_from_slice(op1_drum_json(name, &slices).as_bytes());
+
+    let path = dir.join(format!("{}.aif", name));
+    buffer.write_aiff(&path, OP1_FORMAT.bits_per_sample, &[(*b"APPL", appl)])?;
+    Ok(path)
+}
+
+// Concatenate samples in key-map order, returning the joined audio and slice positions
+fn build_chain(keymap: &KeyMap, format: DeviceFormat, options: &ChainOptions) -> Result<(AudioBuffer, Vec<Slice>)> {
+    let mut parts = Vec::new();
+    for zone in &keymap.zones {
+        let sample = &keymap.samples[zone.sample];
+        let mut buffer = AudioBuffer::read_wav(&sample.path)?.resample(format.sample_rate);
+        if options.normalize {
+            buffer.normalize_peak(NORMALIZE_PEAK);
+        }
+        parts.push((buffer, sample.text.clone()));
+    }
+
+    if parts.is_empty() {
+        return Err(ParasiteError::AudioProcessing("No samples to chain".to_string()).into());
+    }
+
+    // Mix everything to the widest input the device allows
+    let channels = parts.iter()
+        .map(|(b, _)| b.channels)
+        .max()
+        .unwrap_or(1)
+        .min(format.max_channels);
+    let gap_frames = (options.gap.as_secs_f64() * format.sample_rate as f64).round() as usize;
+
+    let mut chain = AudioBuffer { sample_rate: format.sample_rate, channels, samples: Vec::new() };
+    let mut slices = Vec::new();
+    for (buffer, label) in parts {
+        let start = chain.frames() as u32;
+        chain.append(&buffer.with_channels(channels));
+        slices.push(Slice { start, end: chain.frames() as u32, label });
+        chain.append_silence(gap_frames);
+    }
+
+    Ok((chain, slices))
+}
+
+fn frames_to_duration(frames: u32, sample_rate: u32) -> Duration {
+    Duration::from_secs_f64(frames as f64 / sample_rate as f64)
+}
+
+// Drum-kit JSON in the layout the OP-1 expects (24 slots, unused slots empty)
+fn op1_drum_json(name: &str, slices: &[Slice]) -> String {
+    let position = |frame: u32| (frame as u64 * OP1_POSITION_SCALE).min(i32::MAX as u64 - 1);
+
+    let mut start = Vec::new();
+    let mut end = Vec::new();
+    for i in 0..OP1_MAX_SLICES {
+        let (s, e) = slices.get(i)
+            .map(|slice| (position(slice.start), position(slice.end)))
+            .unwrap_or((0, 0));
+        start.push(s.to_string());
+        end.push(e.to_string());
+    }
+
+    let repeated = |value: &str| vec![value; OP1_MAX_SLICES].join(",");
+    let name: String = name.chars().filter(|c| c.is_ascii_alphanumeric() || *c == ' ').take(16).collect();
+
+    format!(
+        concat!(
+            "{{\"drum_version\":2,\"type\":\"drum\",\"name\":\"{}\",\"octave\":0,",
+            "\"pitch\":[{}],\"start\":[{}],\"end\":[{}],",
+            "\"playmode\":[{}],\"reverse\":[{}],\"volume\":[{}],",
+            "\"dyna_env\":[0,8192,0,8192,0,0,0,0],",
+            "\"fx_active\":false,\"fx_type\":\"delay\",\"fx_params\":[8000,8000,8000,8000,8000,8000,8000,8000],",
+            "\"lfo_active\":false,\"lfo_type\":\"tremolo\",\"lfo_params\":[16000,16000,16000,16000,0,0,0,0]}}"
+        ),
+        name,
+        repeated("0"),
+        start.join(","),
+        end.join(","),
+        repeated("8192"),
+        repeated("8192"),
+        repeated("8192"),
+    )
+}
@@ -0,0 +1,107 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use super::{xml_escape, KeyMap};
+
+// Default envelope and level for the generated instrument
+const DEFAULT_VOLUME: f32 = 0.8;
+const DEFAULT_ATTACK: f32 = 0.001;
+const DEFAULT_RELEASE: f32 = 0.3;
+
+// Write a Decent Sampler bundle:
+//   <name>.dsbundle/<name>.dspreset
+//   <name>.dsbundle/Samples/*.wav
+pub fn write_bundle(keymap: &KeyMap, dir: &Path, name: &str) -> Result<PathBuf> {
+    let bundle_dir = dir.join(format!("{}.dsbundle", name));
+    let samples_dir = bundle_dir.join("Samples");
+    fs::create_dir_all(&samples_dir)?;
+
+    for sample in &keymap.samples {
+        if let Some(file_name) = sample.path.file_name() {
+            fs::copy(&sample.path, samples_dir.join(file_name))?;
+        }
+    }
+
+    let preset_path = bundle_dir.join(format!("{}.dspreset", name));
+    fs::write(&preset_path, render(keymap))?;
+    Ok(bundle_dir)
+}
+
+fn render(keymap: &KeyMap) -> String {
+    let mut xml = String::new();
+    let _ = writeln!(xml, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    let _ = writeln!(xml, "<DecentSampler minVersion=\"1.0.0\">");
+
+    render_ui(&mut xml);
+
+    let _ = writeln!(
+        xml,
+        "  <groups volume=\"{}\" attack=\"{}\" decay=\"0\" sustain=\"1\" release=\"{}\">",
+        DEFAULT_VOLUME, DEFAULT_ATTACK, DEFAULT_RELEASE
+    );
+    for group in 0..keymap.group_count() {
+        let zones: Vec<_> = keymap.zones.iter().filter(|z| z.group == group).collect();
+        let Some(first) = zones.first() else { continue };
+
+        let _ = write!(xml, "    <group name=\"{}\"", xml_escape(&keymap.samples[first.sample].text));
+        if first.seq_length > 1 {
+            let _ = write!(xml, " seqMode=\"round_robin\" seqLength=\"{}\"", first.seq_length);
+        }
+        let _ = writeln!(xml, ">");
+
+        for zone in zones {
+            let sample = &keymap.samples[zone.sample];
+            let file_name = sample.path.file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| format!("{}.wav", sample.name));
+
+            let _ = write!(
+                xml,
+                "      <sample path=\"Samples/{}\" rootNote=\"{}\" loNote=\"{}\" hiNote=\"{}\" loVel=\"{}\" hiVel=\"{}\"",
+                xml_escape(&file_name), zone.root_key, zone.lo_key, zone.hi_key, zone.lo_vel, zone.hi_vel
+            );
+            if zone.seq_length > 1 {
+                let _ = write!(xml, " seqPosition=\"{}\"", zone.seq_position);
+            }
+            let _ = writeln!(xml, " />");
+        }
+        let _ = writeln!(xml, "    </group>");
+    }
+    let _ = writeln!(xml, "  </groups>");
+
+    // The tone knob drives this filter's cutoff
+    let _ = writeln!(xml, "  <effects>");
+    let _ = writeln!(xml, "    <effect type=\"lowpass\" frequency=\"22000\" />");
+    let _ = writeln!(xml, "  </effects>");
+    let _ = writeln!(xml, "</DecentSampler>");
+    xml
+}
+
+// Volume, attack, release and tone knobs bound to the instrument
+fn render_ui(xml: &mut String) {
+    let _ = writeln!(xml, "  <ui width=\"812\" height=\"375\">");
+    let _ = writeln!(xml, "    <tab name=\"main\">");
+
+    let knobs = [
+        ("Volume", 0.0, 1.0, DEFAULT_VOLUME, "type=\"amp\" level=\"instrument\" position=\"0\" parameter=\"AMP_VOLUME\""),
+        ("Attack", 0.0, 2.0, DEFAULT_ATTACK, "type=\"amp\" level=\"instrument\" position=\"0\" parameter=\"ENV_ATTACK\""),
+        ("Release", 0.0, 5.0, DEFAULT_RELEASE, "type=\"amp\" level=\"instrument\" position=\"0\" parameter=\"ENV_RELEASE\""),
+        ("Tone", 0.0, 1.0, 1.0, "type=\"effect\" level=\"instrument\" position=\"0\" parameter=\"FX_FILTER_FREQUENCY\" translation=\"table\" translationTable=\"0,60;0.3,800;0.6,4000;1,22000\""),
+    ];
+
+    for (i, (label, min, max, value, binding)) in knobs.iter().enumerate() {
+        let _ = writeln!(
+            xml,
+            "      <labeled-knob x=\"{}\" y=\"40\" width=\"90\" textSize=\"16\" textColor=\"FFFFFFFF\" trackForegroundColor=\"CC000000\" trackBackgroundColor=\"66999999\" label=\"{}\" type=\"float\" minValue=\"{}\" maxValue=\"{}\" value=\"{}\">",
+            20 + i * 100, label, min, max, value
+        );
+        let _ = writeln!(xml, "        <binding {} />", binding);
+        let _ = writeln!(xml, "      </labeled-knob>");
+    }
+
+    let _ = writeln!(xml, "    </tab>");
+    let _ = writeln!(xml, "  </ui>");
+}
@@ -0,0 +1,105 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+use super::{convert_sample, device_file_name, DeviceFormat, KeyMap};
+
+// Tempo written into .ot files; only affects timestretch, which we leave off
+const OT_TEMPO_BPM: u32 = 120;
+// An .ot file has room for this many slices
+pub const OT_MAX_SLICES: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ElektronDevice {
+    /// Digitakt: 48kHz/16-bit mono
+    Digitakt,
+    /// Octatrack: 44.1kHz/16-bit with an .ot attribute file per sample
+    Octatrack,
+}
+
+impl ElektronDevice {
+    fn format(self) -> DeviceFormat {
+        match self {
+            ElektronDevice::Digitakt => DeviceFormat { sample_rate: 48000, bits_per_sample: 16, max_channels: 1 },
+            ElektronDevice::Octatrack => DeviceFormat { sample_rate: 44100, bits_per_sample: 16, max_channels: 2 },
+        }
+    }
+
+    fn folder_suffix(self) -> &'static str {
+        match self {
+            ElektronDevice::Digitakt => "digitakt",
+            ElektronDevice::Octatrack => "octatrack",
+        }
+    }
+}
+
+// Write an Elektron kit folder (<name>_<device>/) of converted samples.
+// Files are prefixed with their pad/slot number so they load in layout order,
+// with a numbered suffix for each layer of a repeated word.
+pub fn write_kit(keymap: &KeyMap, dir: &Path, name: &str, device: ElektronDevice) -> Result<PathBuf> {
+    let kit_dir = dir.join(format!("{}_{}", name, device.folder_suffix()));
+    fs::create_dir_all(&kit_dir)?;
+
+    for group in 0..keymap.group_count() {
+        let zones: Vec<_> = keymap.zones.iter().filter(|z| z.group == group).collect();
+
+        for (layer, zone) in zones.iter().enumerate() {
+            let sample = &keymap.samples[zone.sample];
+            // Padded to the same width so layers still sort in order
+            let suffix = if zones.len() > 1 {
+                format!("-{:0width$}", layer + 1, width = zones.len().to_string().len())
+            } else {
+                String::new()
+            };
+            let file_name = format!("{:02}{}_{}", group + 1, suffix, device_file_name(&sample.text, 20));
+            let wav_path = kit_dir.join(format!("{}.wav", file_name));
+
+            let buffer = convert_sample(&sample.path, &wav_path, device.format())?;
+
+            if device == ElektronDevice::Octatrack {
+                let frames = buffer.frames() as u32;
+                write_ot(&wav_path.with_extension("ot"), buffer.sample_rate, frames, &[(0, frames)])?;
+            }
+        }
+    }
+
+    Ok(kit_dir)
+}
+
+// Write an Octatrack .ot attribute file with the given slices (start, end
+// frames), of which there must be at most OT_MAX_SLICES
+pub fn write_ot(path: &Path, sample_rate: u32, frames: u32, slices: &[(u32, u32)]) -> Result<()> {
+    let bars = (frames as f64 * OT_TEMPO_BPM as f64) / (sample_rate as f64 * 60.0 * 4.0);
+    let bar_length = (bars * 100.0).round() as u32;
+
+    let mut data = Vec::with_capacity(832);
+    data.extend_from_slice(b"FORM\0\0\0\0DPS1SMPA");
+    data.extend_from_slice(&[0, 0, 0, 0, 0, 2, 0]);
+    data.extend_from_slice(&(OT_TEMPO_BPM * 24).to_be_bytes()); // Tempo
+    data.extend_from_slice(&bar_length.to_be_bytes());          // Trim length
+    data.extend_from_slice(&bar_length.to_be_bytes());          // Loop length
+    data.extend_from_slice(&0u32.to_be_bytes());                // Timestretch off
+    data.extend_from_slice(&0u32.to_be_bytes());                // Loop off
+    data.extend_from_slice(&48u16.to_be_bytes());               // Gain (48 = 0dB)
+    data.push(0xFF);                                            // Trig quantization: direct
+    data.extend_from_slice(&0u32.to_be_bytes());                // Trim start
+    data.extend_from_slice(&frames.to_be_bytes());              // Trim end
+    data.extend_from_slice(&0u32.to_be_bytes());                // Loop point
+
+    for i in 0..OT_MAX_SLICES {
+        let (start, end) = slices.get(i).copied().unwrap_or((0, 0));
+        data.extend_from_slice(&start.to_be_bytes());
+        data.extend_from_slice(&end.to_be_bytes());
+        data.extend_from_slice(&0xFFFF_FFFFu32.to_be_bytes()); // Slice loop point: none
+    }
+    data.extend_from_slice(&(slices.len() as u32).to_be_bytes());
+
+    // Checksum covers everything after the 16-byte header
+    let checksum = data[16..].iter().fold(0u16, |sum, b| sum.wrapping_add(*b as u16));
+    data.extend_from_slice(&checksum.to_be_bytes());
+
+    fs::write(path, data)?;
+    Ok(())
+}
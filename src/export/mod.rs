@@ -0,0 +1,454 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clap::ValueEnum;
+use walkdir::WalkDir;
+
+use crate::audio::AudioBuffer;
+use crate::license::SourceLicense;
+use crate::metadata;
+
+use chain::ChainOptions;
+use elektron::ElektronDevice;
+
+pub mod ableton;
+pub mod bitwig;
+pub mod chain;
+pub mod decent_sampler;
+pub mod elektron;
+pub mod mpc;
+pub mod sf2;
+pub mod sfz;
+
+// Lowest MIDI note used when laying samples out across the keyboard (C2)
+pub const DEFAULT_BASE_KEY: u8 = 36;
+
+// Instrument and kit formats parasite can write
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// SFZ instrument (.sfz)
+    Sfz,
+    /// Decent Sampler bundle (.dsbundle with .dspreset and samples)
+    DecentSampler,
+    /// Bitwig multisample (.multisample zip with XML and samples)
+    Bitwig,
+    /// SoundFont (.sf2) with one preset holding every sample
+    Sf2,
+    /// Akai MPC drum program (.xpm) with 16-pad banks
+    Mpc,
+    /// Elektron Digitakt kit folder (48kHz/16-bit mono)
+    Digitakt,
+    /// Elektron Octatrack kit folder with .ot attribute files
+    Octatrack,
+    /// Single WAV slice chain with cue markers and an Octatrack .ot sidecar
+    Chain,
+    /// OP-1 drum kit: AIFF slice chain with slice points in the APPL chunk
+    Op1,
+    /// Ableton Live Drum Rack (.adg) with one Simpler per pad
+    Ableton,
+}
+
+impl ExportFormat {
+    // Name as accepted on the command line
+    pub fn name(self) -> String {
+        self.to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default()
+    }
+
+    // The next format in declaration order, wrapping around
+    pub fn next(self) -> ExportFormat {
+        let formats = ExportFormat::value_variants();
+        let idx = formats.iter().position(|f| *f == self).unwrap_or(0);
+        formats[(idx + 1) % formats.len()]
+    }
+}
+
+// Write a key map in the given format, returning the file or folder created
+pub fn write_export(keymap: &KeyMap, format: ExportFormat, dir: &Path, name: &str, chain: &ChainOptions) -> Result<PathBuf> {
+    match format {
+        ExportFormat::Sfz => sfz::write_sfz(keymap, dir, name),
+        ExportFormat::DecentSampler => decent_sampler::write_bundle(keymap, dir, name),
+        ExportFormat::Bitwig => bitwig::write_multisample(keymap, dir, name),
+        ExportFormat::Sf2 => sf2::write_sf2(keymap, dir, name),
+        ExportFormat::Mpc => mpc::write_program(keymap, dir, name),
+        ExportFormat::Digitakt => elektron::write_kit(keymap, dir, name, ElektronDevice::Digitakt),
+        ExportFormat::Octatrack => elektron::write_kit(keymap, dir, name, ElektronDevice::Octatrack),
+        ExportFormat::Chain => chain::write_wav_chain(keymap, dir, name, chain),
+        ExportFormat::Op1 => chain::write_op1_chain(keymap, dir, name, chain),
+        ExportFormat::Ableton => ableton::write_drum_rack(keymap, dir, name),
+    }
+}
+
+// How samples are laid out across keys
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum KeyLayout {
+    /// One sample per key, ascending from the base key
+    Chromatic,
+    /// Repeated words share a key and alternate as round-robins
+    RoundRobin,
+    /// Repeated words share a key, split into velocity layers by level
+    Velocity,
+}
+
+impl KeyLayout {
+    // Name as accepted on the command line
+    pub fn name(self) -> String {
+        self.to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default()
+    }
+
+    // The next layout in declaration order, wrapping around
+    pub fn next(self) -> KeyLayout {
+        let layouts = KeyLayout::value_variants();
+        let idx = layouts.iter().position(|l| *l == self).unwrap_or(0);
+        layouts[(idx + 1) % layouts.len()]
+    }
+}
+
+// A sample available for export
+#[derive(Clone)]
+pub struct ExportSample {
+    pub path: PathBuf,   // WAV file in the output directory
+    pub name: String,    // File stem
+    pub text: String,    // Transcript text (from INFO INAM, falling back to the name)
+    pub peak: f32,       // Peak level (0.0-1.0), used to order velocity layers
+    pub source: Option<PathBuf>,         // Source recording (from INFO ICMT)
+    pub license: Option<SourceLicense>, // License of the source recording
+}
+
+impl ExportSample {
+    pub fn load(path: &Path) -> Result<ExportSample> {
+        let name = path.file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("sample")
+            .to_string();
+        let text = metadata::read_info_entry(path, b"INAM")
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(|| name.replace('_', " "));
+
+        // Prefer the source's current sidecar, falling back to the license
+        // embedded at extraction time (INFO ICOP)
+        let source = metadata::read_source(path);
+        let license = source.as_deref()
+            .and_then(SourceLicense::for_source)
+            .or_else(|| {
+                metadata::read_info_entry(path, b"ICOP")
+                    .filter(|l| !l.is_empty())
+                    .map(|license| SourceLicense { license, attribution: String::new() })
+            });
+
+        Ok(ExportSample {
+            path: path.to_path_buf(),
+            name,
+            text,
+            peak: AudioBuffer::read_wav(path)?.peak(),
+            source,
+            license,
+        })
+    }
+
+    // Name of the source recording, or of the sample itself if that's unknown
+    pub fn source_name(&self) -> String {
+        self.source.as_deref()
+            .unwrap_or(&self.path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    // Key used to decide whether two samples are "the same word"
+    pub fn group_key(&self) -> String {
+        self.text
+            .to_lowercase()
+            .chars()
+            .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+            .collect::<String>()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+// Each distinct source recording behind `samples`, with its license
+pub fn sample_sources(samples: &[ExportSample]) -> Vec<(String, Option<SourceLicense>)> {
+    let mut sources: Vec<(String, Option<SourceLicense>)> = Vec::new();
+    for sample in samples {
+        let name = sample.source_name();
+        if !sources.iter().any(|(n, _)| *n == name) {
+            sources.push((name, sample.license.clone()));
+        }
+    }
+    sources
+}
+
+// One sample placed on the keyboard
+#[derive(Clone)]
+pub struct Zone {
+    pub sample: usize,       // Index into KeyMap::samples
+    pub lo_key: u8,
+    pub hi_key: u8,
+    pub root_key: u8,
+    pub lo_vel: u8,
+    pub hi_vel: u8,
+    pub group: usize,        // Zones sharing a key form a group
+    pub seq_position: usize, // 1-based round-robin position within the group
+    pub seq_length: usize,   // Number of round-robins in the group (1 = none)
+}
+
+// Velocity runs 1-127, so a key holds at most this many layers
+const MAX_VELOCITY_LAYERS: usize = 127;
+
+// Common key-mapping model shared by all instrument exporters
+pub struct KeyMap {
+    pub samples: Vec<ExportSample>,
+    pub zones: Vec<Zone>,
+    pub unplaced: Vec<usize>, // Samples that didn't fit on the keyboard
+}
+
+impl KeyMap {
+    pub fn build(samples: Vec<ExportSample>, layout: KeyLayout, base_key: u8) -> KeyMap {
+        let mut zones = Vec::new();
+        let mut unplaced = Vec::new();
+
+        match layout {
+            KeyLayout::Chromatic => {
+                for (i, _) in samples.iter().enumerate() {
+                    let Some(key) = key_at(base_key, i) else {
+                        unplaced.push(i);
+                        continue;
+                    };
+                    zones.push(Zone {
+                        sample: i,
+                        lo_key: key,
+                        hi_key: key,
+                        root_key: key,
+                        lo_vel: 1,
+                        hi_vel: 127,
+                        group: i,
+                        seq_position: 1,
+                        seq_length: 1,
+                    });
+                }
+            }
+            KeyLayout::RoundRobin | KeyLayout::Velocity => {
+                for (group, mut members) in group_samples(&samples).into_iter().enumerate() {
+                    let Some(key) = key_at(base_key, group) else {
+                        unplaced.extend(members);
+                        continue;
+                    };
+
+                    if layout == KeyLayout::Velocity {
+                        // Quietest sample on the lowest layer
+                        members.sort_by(|a, b| samples[*a].peak.total_cmp(&samples[*b].peak));
+                        if members.len() > MAX_VELOCITY_LAYERS {
+                            unplaced.extend(members.split_off(MAX_VELOCITY_LAYERS));
+                        }
+                    }
+
+                    let count = members.len();
+                    for (i, sample) in members.into_iter().enumerate() {
+                        let (lo_vel, hi_vel, seq_position, seq_length) = if layout == KeyLayout::Velocity {
+                            let (lo, hi) = velocity_range(i, count);
+                            (lo, hi, 1, 1)
+                        } else {
+                            (1, 127, i + 1, count)
+                        };
+
+                        zones.push(Zone {
+                            sample,
+                            lo_key: key,
+                            hi_key: key,
+                            root_key: key,
+                            lo_vel,
+                            hi_vel,
+                            group,
+                            seq_position,
+                            seq_length,
+                        });
+                    }
+                }
+            }
+        }
+
+        unplaced.sort_unstable();
+        KeyMap { samples, zones, unplaced }
+    }
+
+    // Warning naming the samples left off the keyboard, if any
+    pub fn unplaced_warning(&self) -> Option<String> {
+        if self.unplaced.is_empty() {
+            return None;
+        }
+        let names: Vec<&str> = self.unplaced.iter().map(|&i| self.samples[i].name.as_str()).collect();
+        Some(format!(
+            "{} sample(s) didn't fit on the keyboard and were left out: {}",
+            names.len(),
+            names.join(", ")
+        ))
+    }
+
+    // Number of distinct groups (keys) in use
+    pub fn group_count(&self) -> usize {
+        self.zones.iter().map(|z| z.group + 1).max().unwrap_or(0)
+    }
+}
+
+fn key_at(base_key: u8, offset: usize) -> Option<u8> {
+    let key = base_key as usize + offset;
+    if key <= 127 { Some(key as u8) } else { None }
+}
+
+// Split 1-127 into `count` contiguous velocity layers
+fn velocity_range(layer: usize, count: usize) -> (u8, u8) {
+    let lo = 1 + (127 * layer) / count;
+    let hi = (127 * (layer + 1)) / count;
+    (lo as u8, hi as u8)
+}
+
+// Group sample indices by word, preserving first-seen order
+fn group_samples(samples: &[ExportSample]) -> Vec<Vec<usize>> {
+    let mut keys: Vec<String> = Vec::new();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+
+    for (i, sample) in samples.iter().enumerate() {
+        let key = sample.group_key();
+        match keys.iter().position(|k| *k == key) {
+            Some(g) => groups[g].push(i),
+            None => {
+                keys.push(key);
+                groups.push(vec![i]);
+            }
+        }
+    }
+    groups
+}
+
+// Escape text for use in XML attribute values
+pub fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// Load every WAV in a directory (non-recursive), sorted by name
+pub fn samples_in_dir(dir: &str) -> Result<Vec<ExportSample>> {
+    let mut paths = WalkDir::new(dir)
+        .max_depth(1)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "wav"))
+        .map(|e| e.path().to_path_buf())
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    paths.iter().map(|p| ExportSample::load(p)).collect()
+}
+
+// Sample format constraints of a target device
+#[derive(Clone, Copy)]
+pub struct DeviceFormat {
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    pub max_channels: u16,
+}
+
+// Convert a sample to a device's format, writing it to `dest`
+pub fn convert_sample(src: &Path, dest: &Path, format: DeviceFormat) -> Result<AudioBuffer> {
+    let mut buffer = AudioBuffer::read_wav(src)?;
+    if buffer.channels > format.max_channels {
+        buffer = buffer.to_mono().with_channels(format.max_channels);
+    }
+    let buffer = buffer.resample(format.sample_rate);
+    buffer.write_wav(dest, format.bits_per_sample, false)?;
+    Ok(buffer)
+}
+
+// Reduce text to a short ASCII file name safe for hardware samplers
+pub fn device_file_name(text: &str, max_len: usize) -> String {
+    let name: String = text
+        .split_whitespace()
+        .map(|w| w.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+        .to_lowercase();
+
+    let name: String = name.chars().take(max_len).collect();
+    if name.is_empty() { "sample".to_string() } else { name }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(text: &str, peak: f32) -> ExportSample {
+        ExportSample {
+            path: PathBuf::from(format!("{}.wav", text)),
+            name: text.to_string(),
+            text: text.to_string(),
+            peak,
+            source: None,
+            license: None,
+        }
+    }
+
+    #[test]
+    fn chromatic_places_one_sample_per_key() {
+        let keymap = KeyMap::build(vec![sample("a", 1.0), sample("b", 1.0), sample("a", 1.0)], KeyLayout::Chromatic, 60);
+        let keys: Vec<u8> = keymap.zones.iter().map(|z| z.root_key).collect();
+        assert_eq!(keys, [60, 61, 62]);
+        assert!(keymap.zones.iter().all(|z| z.lo_vel == 1 && z.hi_vel == 127 && z.seq_length == 1));
+        assert_eq!(keymap.group_count(), 3);
+    }
+
+    #[test]
+    fn round_robin_groups_repeated_words_on_one_key() {
+        let samples = vec![sample("Hello", 1.0), sample("world", 1.0), sample("hello!", 1.0)];
+        let keymap = KeyMap::build(samples, KeyLayout::RoundRobin, 36);
+        let zones: Vec<(usize, u8, usize, usize)> = keymap.zones.iter()
+            .map(|z| (z.sample, z.root_key, z.seq_position, z.seq_length))
+            .collect();
+        assert_eq!(zones, [(0, 36, 1, 2), (2, 36, 2, 2), (1, 37, 1, 1)]);
+        assert_eq!(keymap.group_count(), 2);
+    }
+
+    #[test]
+    fn velocity_layers_split_the_range_quietest_first() {
+        let samples = vec![sample("yes", 0.9), sample("yes", 0.1), sample("yes", 0.5)];
+        let keymap = KeyMap::build(samples, KeyLayout::Velocity, 36);
+        let layers: Vec<(usize, u8, u8)> = keymap.zones.iter().map(|z| (z.sample, z.lo_vel, z.hi_vel)).collect();
+        assert_eq!(layers, [(1, 1, 42), (2, 43, 84), (0, 85, 127)]);
+    }
+
+    #[test]
+    fn samples_past_the_top_key_are_unplaced() {
+        let samples = (0..5).map(|i| sample(&format!("word{}", i), 1.0)).collect();
+        let keymap = KeyMap::build(samples, KeyLayout::Chromatic, 125);
+        assert_eq!(keymap.zones.iter().map(|z| z.root_key).collect::<Vec<_>>(), [125, 126, 127]);
+        assert_eq!(keymap.unplaced, [3, 4]);
+        assert!(keymap.unplaced_warning().unwrap().contains("word3, word4"));
+    }
+
+    #[test]
+    fn velocity_layers_are_capped_at_127() {
+        let samples = (0..130).map(|i| sample("again", i as f32 / 130.0)).collect();
+        let keymap = KeyMap::build(samples, KeyLayout::Velocity, 36);
+        assert_eq!(keymap.zones.len(), 127);
+        // The loudest don't fit; every layer keeps at least one velocity
+        assert_eq!(keymap.unplaced, [127, 128, 129]);
+        assert!(keymap.zones.iter().all(|z| z.lo_vel <= z.hi_vel));
+        assert_eq!(keymap.zones.last().map(|z| z.hi_vel), Some(127));
+    }
+
+    #[test]
+    fn empty_input_builds_an_empty_map() {
+        let keymap = KeyMap::build(Vec::new(), KeyLayout::RoundRobin, 36);
+        assert!(keymap.zones.is_empty());
+        assert_eq!(keymap.group_count(), 0);
+        assert!(keymap.unplaced_warning().is_none());
+    }
+}
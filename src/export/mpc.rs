@@ -0,0 +1,150 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use super::{convert_sample, device_file_name, xml_escape, DeviceFormat, KeyMap};
+use crate::ParasiteError;
+
+// MPC pads hold 16 per bank across banks A-H
+const PADS_PER_BANK: usize = 16;
+const MAX_PADS: usize = PADS_PER_BANK * 8;
+// Each pad plays up to four layers
+const MAX_LAYERS: usize = 4;
+
+const MPC_FORMAT: DeviceFormat = DeviceFormat {
+    sample_rate: 44100,
+    bits_per_sample: 16,
+    max_channels: 2,
+};
+
+// Write an MPC drum program folder:
+//   <name>_mpc/<name>.xpm
+//   <name>_mpc/*.wav (44.1kHz/16-bit)
+// Each key-map group becomes a pad; its zones become the pad's layers.
+// Refuses key maps with more pads or layers than a program holds rather than
+// leaving samples out.
+pub fn write_program(keymap: &KeyMap, dir: &Path, name: &str) -> Result<PathBuf> {
+    if keymap.group_count() > MAX_PADS {
+        return Err(ParasiteError::AudioProcessing(format!(
+            "{} keys won't fit an MPC program's {} pads",
+            keymap.group_count(),
+            MAX_PADS
+        )).into());
+    }
+    for group in 0..keymap.group_count() {
+        let zones: Vec<_> = keymap.zones.iter().filter(|z| z.group == group).collect();
+        if zones.len() > MAX_LAYERS {
+            let text = &keymap.samples[zones[0].sample].text;
+            return Err(ParasiteError::AudioProcessing(format!(
+                "\"{}\" has {} takes but an MPC pad holds {} layers; remove some or use the chromatic layout",
+                text,
+                zones.len(),
+                MAX_LAYERS
+            )).into());
+        }
+    }
+
+    let program_dir = dir.join(format!("{}_mpc", name));
+    fs::create_dir_all(&program_dir)?;
+
+    let mut pads: Vec<Pad> = Vec::new();
+    for group in 0..keymap.group_count() {
+        let zones: Vec<_> = keymap.zones.iter().filter(|z| z.group == group).collect();
+        let Some(first) = zones.first() else { continue };
+
+        let mut layers = Vec::new();
+        for zone in &zones {
+            let sample = &keymap.samples[zone.sample];
+            let sample_name = format!("{:03}_{}", group + 1, device_file_name(&sample.text, 24));
+            let sample_name = if zones.len() > 1 {
+                format!("{}_{}", sample_name, layers.len() + 1)
+            } else {
+                sample_name
+            };
+
+            convert_sample(&sample.path, &program_dir.join(format!("{}.wav", sample_name)), MPC_FORMAT)?;
+            layers.push(Layer {
+                sample_name,
+                vel_start: zone.lo_vel,
+                vel_end: zone.hi_vel,
+            });
+        }
+
+        pads.push(Pad {
+            note: first.root_key,
+            // Round-robin groups cycle through their layers
+            cycle: first.seq_length > 1,
+            layers,
+        });
+    }
+
+    let program_path = program_dir.join(format!("{}.xpm", name));
+    fs::write(&program_path, render(name, &pads))?;
+    Ok(program_dir)
+}
+
+struct Pad {
+    note: u8,
+    cycle: bool,
+    layers: Vec<Layer>,
+}
+
+struct Layer {
+    sample_name: String,
+    vel_start: u8,
+    vel_end: u8,
+}
+
+fn render(name: &str, pads: &[Pad]) -> String {
+    let mut xml = String::new();
+    let _ = writeln!(xml, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    let _ = writeln!(xml, "<MPCVObject>");
+    let _ = writeln!(xml, "  <Version>");
+    let _ = writeln!(xml, "    <File_Version>2.1</File_Version>");
+    let _ = writeln!(xml, "    <Application>MPC-V</Application>");
+    let _ = writeln!(xml, "    <Application_Version>2.10.0.0</Application_Version>");
+    let _ = writeln!(xml, "    <Platform>Linux</Platform>");
+    let _ = writeln!(xml, "  </Version>");
+    let _ = writeln!(xml, "  <Program type=\"Drum\">");
+    let _ = writeln!(xml, "    <ProgramName>{}</ProgramName>", xml_escape(name));
+    let _ = writeln!(xml, "    <Instruments>");
+
+    for (i, pad) in pads.iter().enumerate() {
+        let _ = writeln!(xml, "      <Instrument number=\"{}\">", i + 1);
+        // ZonePlay: 0 = velocity, 1 = cycle
+        let _ = writeln!(xml, "        <ZonePlay>{}</ZonePlay>", if pad.cycle { 1 } else { 0 });
+        let _ = writeln!(xml, "        <OneShot>True</OneShot>");
+        let _ = writeln!(xml, "        <Layers>");
+        for (j, layer) in pad.layers.iter().enumerate() {
+            let _ = writeln!(xml, "          <Layer number=\"{}\">", j + 1);
+            let _ = writeln!(xml, "            <Active>True</Active>");
+            let _ = writeln!(xml, "            <Volume>1.000000</Volume>");
+            let _ = writeln!(xml, "            <Pan>0.500000</Pan>");
+            let _ = writeln!(xml, "            <Pitch>0.000000</Pitch>");
+            let _ = writeln!(xml, "            <VelStart>{}</VelStart>", layer.vel_start);
+            let _ = writeln!(xml, "            <VelEnd>{}</VelEnd>", layer.vel_end);
+            let _ = writeln!(xml, "            <SampleName>{}</SampleName>", xml_escape(&layer.sample_name));
+            let _ = writeln!(xml, "            <SampleFile></SampleFile>");
+            let _ = writeln!(xml, "            <SliceIndex>128</SliceIndex>");
+            let _ = writeln!(xml, "            <RootNote>0</RootNote>");
+            let _ = writeln!(xml, "            <KeyTrack>False</KeyTrack>");
+            let _ = writeln!(xml, "          </Layer>");
+        }
+        let _ = writeln!(xml, "        </Layers>");
+        let _ = writeln!(xml, "      </Instrument>");
+    }
+
+    let _ = writeln!(xml, "    </Instruments>");
+    let _ = writeln!(xml, "    <PadNoteMap>");
+    for (i, pad) in pads.iter().enumerate() {
+        let _ = writeln!(xml, "      <PadNote number=\"{}\">", i + 1);
+        let _ = writeln!(xml, "        <Note>{}</Note>", pad.note);
+        let _ = writeln!(xml, "      </PadNote>");
+    }
+    let _ = writeln!(xml, "    </PadNoteMap>");
+    let _ = writeln!(xml, "  </Program>");
+    let _ = writeln!(xml, "</MPCVObject>");
+    xml
+}
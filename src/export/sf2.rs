@@ -0,0 +1,173 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::audio::AudioBuffer;
+
+use super::{velocity_range, KeyMap};
+
+// SF2 generator operators used by the instrument zones
+const GEN_INSTRUMENT: u16 = 41;
+const GEN_KEY_RANGE: u16 = 43;
+const GEN_VEL_RANGE: u16 = 44;
+const GEN_SAMPLE_ID: u16 = 53;
+const GEN_SAMPLE_MODES: u16 = 54;
+const GEN_OVERRIDING_ROOT_KEY: u16 = 58;
+
+// Generators per instrument zone (keyRange, velRange, sampleModes, rootKey, sampleID)
+const GENS_PER_ZONE: usize = 5;
+// The spec requires at least 46 zero samples after each sample
+const SAMPLE_PADDING: usize = 46;
+
+// Write `<name>.sf2` with one preset whose instrument holds every zone.
+// SoundFonts have no round-robin, so repeated words are spread across velocity.
+pub fn write_sf2(keymap: &KeyMap, dir: &Path, name: &str) -> Result<PathBuf> {
+    let mut smpl: Vec<u8> = Vec::new();
+    let mut shdr: Vec<u8> = Vec::new();
+
+    for sample in &keymap.samples {
+        let buffer = AudioBuffer::read_wav(&sample.path)?.to_mono();
+        let start = smpl.len() / 2;
+        for &s in &buffer.samples {
+            let value = (s.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+            smpl.extend_from_slice(&value.to_le_bytes());
+        }
+        let end = smpl.len() / 2;
+        smpl.resize(smpl.len() + SAMPLE_PADDING * 2, 0);
+
+        let root_key = keymap.zones.iter()
+            .find(|z| keymap.samples[z.sample].path == sample.path)
+            .map(|z| z.root_key)
+            .unwrap_or(60);
+
+        shdr.extend(fixed_name(&sample.name));
+        shdr.extend_from_slice(&(start as u32).to_le_bytes());
+        shdr.extend_from_slice(&(end as u32).to_le_bytes());
+        shdr.extend_from_slice(&(start as u32).to_le_bytes()); // Loop start (unused)
+        shdr.extend_from_slice(&(end as u32).to_le_bytes());   // Loop end (unused)
+        shdr.extend_from_slice(&buffer.sample_rate.to_le_bytes());
+        shdr.push(root_key);
+        shdr.push(0);                                          // Pitch correction
+        shdr.extend_from_slice(&0u16.to_le_bytes());           // Sample link
+        shdr.extend_from_slice(&1u16.to_le_bytes());           // Mono sample
+    }
+    shdr.extend(fixed_name("EOS"));
+    shdr.extend_from_slice(&[0u8; 26]);
+
+    // Instrument zones, one per key-map zone
+    let mut ibag = Vec::new();
+    let mut igen = Vec::new();
+    for (i, zone) in keymap.zones.iter().enumerate() {
+        let (lo_vel, hi_vel) = if zone.seq_length > 1 {
+            velocity_range(zone.seq_position - 1, zone.seq_length)
+        } else {
+            (zone.lo_vel, zone.hi_vel)
+        };
+
+        ibag.extend(bag((i * GENS_PER_ZONE) as u16));
+        igen.extend(range_gen(GEN_KEY_RANGE, zone.lo_key, zone.hi_key));
+        igen.extend(range_gen(GEN_VEL_RANGE, lo_vel, hi_vel));
+        igen.extend(amount_gen(GEN_SAMPLE_MODES, 0));
+        igen.extend(amount_gen(GEN_OVERRIDING_ROOT_KEY, zone.root_key as u16));
+        igen.extend(amount_gen(GEN_SAMPLE_ID, zone.sample as u16));
+    }
+    ibag.extend(bag((keymap.zones.len() * GENS_PER_ZONE) as u16));
+    igen.extend(amount_gen(0, 0));
+
+    let mut inst = fixed_name(name);
+    inst.extend_from_slice(&0u16.to_le_bytes());
+    inst.extend(fixed_name("EOI"));
+    inst.extend_from_slice(&(keymap.zones.len() as u16).to_le_bytes());
+
+    // A single preset pointing at the instrument
+    let mut phdr = fixed_name(name);
+    phdr.extend_from_slice(&0u16.to_le_bytes()); // Preset number
+    phdr.extend_from_slice(&0u16.to_le_bytes()); // Bank
+    phdr.extend_from_slice(&0u16.to_le_bytes()); // Bag index
+    phdr.extend_from_slice(&[0u8; 12]);          // Library, genre, morphology
+    phdr.extend(fixed_name("EOP"));
+    phdr.extend_from_slice(&0u16.to_le_bytes());
+    phdr.extend_from_slice(&0u16.to_le_bytes());
+    phdr.extend_from_slice(&1u16.to_le_bytes());
+    phdr.extend_from_slice(&[0u8; 12]);
+
+    let mut pbag = bag(0);
+    pbag.extend(bag(1));
+    let mut pgen = amount_gen(GEN_INSTRUMENT, 0);
+    pgen.extend(amount_gen(0, 0));
+
+    let mut info = b"INFO".to_vec();
+    info.extend(sub_chunk(b"ifil", &[2, 0, 1, 0]));
+    info.extend(sub_chunk(b"isng", &zstr("EMU8000")));
+    info.extend(sub_chunk(b"INAM", &zstr(name)));
+    info.extend(sub_chunk(b"ISFT", &zstr("parasite")));
+
+    let mut sdta = b"sdta".to_vec();
+    sdta.extend(sub_chunk(b"smpl", &smpl));
+
+    let mut pdta = b"pdta".to_vec();
+    pdta.extend(sub_chunk(b"phdr", &phdr));
+    pdta.extend(sub_chunk(b"pbag", &pbag));
+    pdta.extend(sub_chunk(b"pmod", &[0u8; 10]));
+    pdta.extend(sub_chunk(b"pgen", &pgen));
+    pdta.extend(sub_chunk(b"inst", &inst));
+    pdta.extend(sub_chunk(b"ibag", &ibag));
+    pdta.extend(sub_chunk(b"imod", &[0u8; 10]));
+    pdta.extend(sub_chunk(b"igen", &igen));
+    pdta.extend(sub_chunk(b"shdr", &shdr));
+
+    let mut body = b"sfbk".to_vec();
+    body.extend(sub_chunk(b"LIST", &info));
+    body.extend(sub_chunk(b"LIST", &sdta));
+    body.extend(sub_chunk(b"LIST", &pdta));
+
+    let path = dir.join(format!("{}.sf2", name));
+    std::fs::write(&path, sub_chunk(b"RIFF", &body))?;
+    Ok(path)
+}
+
+fn sub_chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = id.to_vec();
+    chunk.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        chunk.push(0);
+    }
+    chunk
+}
+
+// NUL-terminated, even-length string for INFO sub-chunks
+fn zstr(value: &str) -> Vec<u8> {
+    let mut bytes: Vec<u8> = value.bytes().filter(|b| b.is_ascii()).take(255).collect();
+    bytes.push(0);
+    if bytes.len() % 2 == 1 {
+        bytes.push(0);
+    }
+    bytes
+}
+
+// 20-byte NUL-padded name used by phdr, inst and shdr records
+fn fixed_name(value: &str) -> Vec<u8> {
+    let mut bytes: Vec<u8> = value.bytes().filter(|b| b.is_ascii()).take(19).collect();
+    bytes.resize(20, 0);
+    bytes
+}
+
+fn bag(gen_index: u16) -> Vec<u8> {
+    let mut record = gen_index.to_le_bytes().to_vec();
+    record.extend_from_slice(&0u16.to_le_bytes()); // Modulator index
+    record
+}
+
+fn amount_gen(operator: u16, amount: u16) -> Vec<u8> {
+    let mut record = operator.to_le_bytes().to_vec();
+    record.extend_from_slice(&amount.to_le_bytes());
+    record
+}
+
+fn range_gen(operator: u16, lo: u8, hi: u8) -> Vec<u8> {
+    let mut record = operator.to_le_bytes().to_vec();
+    record.push(lo);
+    record.push(hi);
+    record
+}
@@ -0,0 +1,55 @@
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use super::KeyMap;
+
+// Write an .sfz instrument next to the samples it references
+pub fn write_sfz(keymap: &KeyMap, dir: &Path, name: &str) -> Result<PathBuf> {
+    let path = dir.join(format!("{}.sfz", name));
+    std::fs::write(&path, render(keymap, name))?;
+    Ok(path)
+}
+
+fn render(keymap: &KeyMap, name: &str) -> String {
+    let mut sfz = String::new();
+    let _ = writeln!(sfz, "// {} - generated by parasite", name);
+    let _ = writeln!(sfz);
+    let _ = writeln!(sfz, "<control>");
+    let _ = writeln!(sfz, "default_path=./");
+    let _ = writeln!(sfz);
+    let _ = writeln!(sfz, "<global>");
+    let _ = writeln!(sfz, "loop_mode=one_shot");
+
+    for group in 0..keymap.group_count() {
+        let zones: Vec<_> = keymap.zones.iter().filter(|z| z.group == group).collect();
+        let Some(first) = zones.first() else { continue };
+
+        let _ = writeln!(sfz);
+        let _ = write!(sfz, "<group> // {}", keymap.samples[first.sample].text);
+        if first.seq_length > 1 {
+            let _ = write!(sfz, "\nseq_length={}", first.seq_length);
+        }
+        let _ = writeln!(sfz);
+
+        for zone in zones {
+            let sample = &keymap.samples[zone.sample];
+            let file_name = sample.path.file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or(&sample.name);
+
+            let _ = write!(
+                sfz,
+                "<region> sample={} lokey={} hikey={} pitch_keycenter={} lovel={} hivel={}",
+                file_name, zone.lo_key, zone.hi_key, zone.root_key, zone.lo_vel, zone.hi_vel
+            );
+            if zone.seq_length > 1 {
+                let _ = write!(sfz, " seq_position={}", zone.seq_position);
+            }
+            let _ = writeln!(sfz);
+        }
+    }
+
+    sfz
+}
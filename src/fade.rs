@@ -0,0 +1,195 @@
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+use crate::audio::AudioBuffer;
+use crate::config::Config;
+use crate::ParasiteError;
+
+// How far from a boundary to look for a zero crossing
+const ZERO_CROSSING_WINDOW: Duration = Duration::from_millis(10);
+// Steepness of the exponential curve
+const EXPONENTIAL_STEEPNESS: f64 = 4.0;
+
+// Per-sample fade lengths offered when cycling overrides in the TUI
+pub const FADE_PRESETS_MS: [u64; 3] = [5, 20, 50];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum FadeCurve {
+    /// Straight-line gain ramp
+    Linear,
+    /// Constant-power (quarter sine) ramp
+    #[default]
+    EqualPower,
+    /// Slow start, fast finish; sounds natural on decaying tails
+    Exponential,
+}
+
+impl FadeCurve {
+    pub fn name(self) -> &'static str {
+        match self {
+            FadeCurve::Linear => "linear",
+            FadeCurve::EqualPower => "equal-power",
+            FadeCurve::Exponential => "exponential",
+        }
+    }
+
+    pub fn next(self) -> FadeCurve {
+        match self {
+            FadeCurve::Linear => FadeCurve::EqualPower,
+            FadeCurve::EqualPower => FadeCurve::Exponential,
+            FadeCurve::Exponential => FadeCurve::Linear,
+        }
+    }
+
+    // Gain at position `x` (0.0-1.0) through a fade-in
+    fn gain(self, x: f64) -> f64 {
+        match self {
+            FadeCurve::Linear => x,
+            FadeCurve::EqualPower => (x * std::f64::consts::FRAC_PI_2).sin(),
+            FadeCurve::Exponential => {
+                (EXPONENTIAL_STEEPNESS * x).exp_m1() / EXPONENTIAL_STEEPNESS.exp_m1()
+            }
+        }
+    }
+}
+
+// Declick settings applied to each extracted sample
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FadeOptions {
+    pub fade_in: Duration,
+    pub fade_out: Duration,
+    pub curve: FadeCurve,
+    pub zero_crossing: bool, // Move boundaries to the nearest zero crossing
+}
+
+impl FadeOptions {
+    // Defaults from the `[fade]` section: `in_ms`, `out_ms`, `curve` and `zero_crossing`
+    pub fn from_config(config: &Config) -> Result<FadeOptions> {
+        let curve = match config.get("fade.curve") {
+            Some(name) => FadeCurve::from_str(name, true)
+                .map_err(|_| ParasiteError::Config(format!("invalid value for fade.curve: '{}'", name)))?,
+            None => FadeCurve::default(),
+        };
+
+        Ok(FadeOptions {
+            fade_in: config.get_ms("fade.in_ms")?.unwrap_or_default(),
+            fade_out: config.get_ms("fade.out_ms")?.unwrap_or_default(),
+            curve,
+            zero_crossing: config.get_bool("fade.zero_crossing")?.unwrap_or(false),
+        })
+    }
+
+    pub fn has_fades(&self) -> bool {
+        !self.fade_in.is_zero() || !self.fade_out.is_zero()
+    }
+
+    // Fade the buffer's edges
+    pub fn apply_to(&self, buffer: &mut AudioBuffer) {
+        let to_frames = |d: Duration| (d.as_secs_f64() * buffer.sample_rate as f64).round() as usize;
+        let (fade_in, fade_out) = (to_frames(self.fade_in), to_frames(self.fade_out));
+        apply(buffer, fade_in, fade_out, self.curve);
+    }
+}
+
+// Per-sample changes to the default fades; `None` keeps the default
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FadeOverride {
+    pub length: Option<Duration>, // Fade-in and fade-out length
+    pub curve: Option<FadeCurve>,
+}
+
+impl FadeOverride {
+    pub fn is_set(&self) -> bool {
+        self.length.is_some() || self.curve.is_some()
+    }
+
+    pub fn apply(&self, defaults: &FadeOptions) -> FadeOptions {
+        FadeOptions {
+            fade_in: self.length.unwrap_or(defaults.fade_in),
+            fade_out: self.length.unwrap_or(defaults.fade_out),
+            curve: self.curve.unwrap_or(defaults.curve),
+            zero_crossing: defaults.zero_crossing,
+        }
+    }
+
+    // Step through default, off and the preset lengths
+    pub fn cycle_length(&mut self) {
+        let presets: Vec<Duration> = FADE_PRESETS_MS.iter().map(|&ms| Duration::from_millis(ms)).collect();
+        self.length = match self.length {
+            None => Some(Duration::ZERO),
+            Some(length) => presets.iter().copied().find(|&p| p > length),
+        };
+    }
+
+    // Step through default and each curve
+    pub fn cycle_curve(&mut self) {
+        self.curve = match self.curve {
+            None => Some(FadeCurve::Linear),
+            Some(FadeCurve::Exponential) => None,
+            Some(curve) => Some(curve.next()),
+        };
+    }
+
+    pub fn describe(&self) -> String {
+        let length = match self.length {
+            Some(length) if length.is_zero() => "off".to_string(),
+            Some(length) => format!("{}ms", length.as_millis()),
+            None => "default".to_string(),
+        };
+        let curve = self.curve.map(FadeCurve::name).unwrap_or("default");
+        format!("fades {}, curve {}", length, curve)
+    }
+}
+
+// Move `start` and `end` to the nearest zero crossings in `source`, searching
+// a few milliseconds either side. Boundaries with no crossing nearby stay put.
+pub fn snap_to_zero_crossings(source: &Path, start: Duration, end: Duration) -> Result<(Duration, Duration)> {
+    let snapped_start = nearest_zero_crossing(source, start)?.unwrap_or(start);
+    let snapped_end = nearest_zero_crossing(source, end)?.unwrap_or(end);
+
+    if snapped_end <= snapped_start {
+        return Ok((start, end));
+    }
+    Ok((snapped_start, snapped_end))
+}
+
+fn nearest_zero_crossing(source: &Path, time: Duration) -> Result<Option<Duration>> {
+    let window_start = time.saturating_sub(ZERO_CROSSING_WINDOW);
+    let buffer = AudioBuffer::read_wav_range(source, window_start, time + ZERO_CROSSING_WINDOW)?.to_mono();
+    let rate = buffer.sample_rate as f64;
+    let target = ((time - window_start).as_secs_f64() * rate).round() as usize;
+
+    // A crossing sits between samples of opposite sign (or on an exact zero)
+    let crossing = buffer.samples
+        .windows(2)
+        .enumerate()
+        .filter(|(_, pair)| pair[0] == 0.0 || (pair[0] < 0.0) != (pair[1] < 0.0))
+        .map(|(i, pair)| if pair[0] == 0.0 { i } else { i + 1 })
+        .min_by_key(|&i| i.abs_diff(target));
+
+    Ok(crossing.map(|i| window_start + Duration::from_secs_f64(i as f64 / rate)))
+}
+
+// Fade the first `fade_in` and last `fade_out` frames along `curve`
+pub fn apply(buffer: &mut AudioBuffer, fade_in: usize, fade_out: usize, curve: FadeCurve) {
+    let channels = buffer.channels as usize;
+    let total = buffer.frames();
+    let fade_in = fade_in.min(total);
+    let fade_out = fade_out.min(total);
+
+    for i in 0..fade_in {
+        let gain = curve.gain(i as f64 / fade_in as f64) as f32;
+        for c in 0..channels {
+            buffer.samples[i * channels + c] *= gain;
+        }
+    }
+    for i in 0..fade_out {
+        let gain = curve.gain(i as f64 / fade_out as f64) as f32;
+        for c in 0..channels {
+            buffer.samples[(total - 1 - i) * channels + c] *= gain;
+        }
+    }
+}
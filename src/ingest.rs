@@ -0,0 +1,378 @@
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::UNIX_EPOCH;
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::master;
+use crate::namer::{self, NamerOptions};
+use crate::ParasiteError;
+
+// Record of finished inputs, kept in the input directory so interrupted
+// batches can be resumed
+const STATE_FILE: &str = ".parasite-ingest";
+// Per-input scratch space for partly finished work
+const WORK_DIR: &str = ".ingest";
+
+// Defaults matching the layout `process.sh` assumed
+const DEFAULT_WHISPER: &str = "~/dev/whisper.cpp/whisper";
+const DEFAULT_MODEL: &str = "~/dev/whisper.cpp/models/ggml-large-v3.bin";
+const DEFAULT_TEMPLATE: &str = "{title}";
+
+// Settings from the `[ingest]` section
+#[derive(Clone, Debug)]
+pub struct IngestOptions {
+    pub whisper: PathBuf,         // whisper.cpp binary
+    pub model: PathBuf,           // ggml model file
+    pub language: Option<String>, // Spoken language, else whisper detects it
+    pub extra_args: Vec<String>,  // Further whisper arguments
+    pub json: bool,               // Also write whisper's JSON transcript
+    pub master: bool,             // Keep the original quality as `<name>.master.wav`
+    pub namer: Option<String>,    // Shell command that reads the transcript and prints a title
+    pub keywords: NamerOptions,   // Built-in keyword namer, used when no command is set
+    pub template: String,         // Output name with {title}, {keywords}, {stem}, {date} and {index}
+    pub force: bool,              // Redo inputs that were already ingested
+}
+
+impl IngestOptions {
+    pub fn from_config(config: &Config) -> Result<IngestOptions> {
+        Ok(IngestOptions {
+            whisper: expand_home(config.get("ingest.whisper").unwrap_or(DEFAULT_WHISPER)),
+            model: expand_home(config.get("ingest.model").unwrap_or(DEFAULT_MODEL)),
+            language: config.get("ingest.language").map(str::to_string),
+            extra_args: config.get("ingest.args")
+                .map(|args| args.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_default(),
+            json: config.get_bool("ingest.json")?.unwrap_or(true),
+            master: config.get_bool("ingest.master")?.unwrap_or(true),
+            namer: config.get("ingest.namer").map(str::to_string),
+            keywords: NamerOptions::from_config(config)?,
+            template: config.get("ingest.name_template").unwrap_or(DEFAULT_TEMPLATE).to_string(),
+            force: false,
+        })
+    }
+
+    // Catch a missing model or binary before starting a long batch
+    fn check(&self) -> Result<()> {
+        if !self.model.exists() {
+            return Err(ParasiteError::Config(format!(
+                "whisper model not found at {} (set `model` under [ingest])",
+                self.model.display()
+            )).into());
+        }
+        if self.whisper.components().count() > 1 && !self.whisper.exists() {
+            return Err(ParasiteError::Config(format!(
+                "whisper binary not found at {} (set `whisper` under [ingest])",
+                self.whisper.display()
+            )).into());
+        }
+        Ok(())
+    }
+}
+
+// How a batch went
+#[derive(Debug, Default)]
+pub struct IngestSummary {
+    pub ingested: Vec<String>, // Names given to the new recordings
+    pub skipped: usize,        // Already ingested in an earlier run
+    pub failed: usize,
+}
+
+// Identifies an input file by path, size and modification time, so a changed
+// file is ingested again
+struct InputKey(String);
+
+impl InputKey {
+    fn of(path: &Path) -> Result<InputKey> {
+        let path = path.canonicalize()?;
+        let meta = std::fs::metadata(&path)?;
+        let modified = meta.modified()?.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        Ok(InputKey(format!("{}\t{}\t{}", path.display(), meta.len(), modified)))
+    }
+
+    // Short stable name for the input's work directory
+    fn digest(&self) -> String {
+        Sha256::digest(self.0.as_bytes())
+            .iter()
+            .take(6)
+            .fold(String::new(), |mut hex, b| {
+                let _ = write!(hex, "{:02x}", b);
+                hex
+            })
+    }
+}
+
+// Convert, transcribe and name each input, writing `<name>.wav` (16kHz mono,
+// as transcribed), `<name>.vtt`, optionally `<name>.json` and
+// `<name>.master.wav` into the input directory. Each step's result is kept
+// until the input is finished, so a rerun picks up where it stopped.
+pub fn ingest(inputs: &[PathBuf], input_dir: &Path, options: &IngestOptions) -> Result<IngestSummary> {
+    options.check()?;
+    std::fs::create_dir_all(input_dir)?;
+
+    let state_path = input_dir.join(STATE_FILE);
+    let state = std::fs::read_to_string(&state_path).unwrap_or_default();
+    let mut summary = IngestSummary::default();
+
+    for (i, input) in inputs.iter().enumerate() {
+        println!("[{}/{}] {}", i + 1, inputs.len(), input.display());
+
+        let key = match InputKey::of(input) {
+            Ok(key) => key,
+            Err(e) => {
+                println!("  failed: cannot read input: {}", e);
+                summary.failed += 1;
+                continue;
+            }
+        };
+        let done = state.lines().find_map(|line| line.strip_suffix(&key.0)?.strip_suffix('\t'));
+        if let (Some(name), false) = (done, options.force) {
+            println!("  already ingested as {}, skipping", name);
+            summary.skipped += 1;
+            continue;
+        }
+
+        match ingest_one(input, i + 1, &key, done, input_dir, options) {
+            Ok(name) => {
+                let mut state_file = std::fs::OpenOptions::new().create(true).append(true).open(&state_path)?;
+                writeln!(state_file, "{}\t{}", name, key.0)?;
+                println!("  done: {}", name);
+                summary.ingested.push(name);
+            }
+            Err(e) => {
+                println!("  failed: {}", e);
+                summary.failed += 1;
+            }
+        }
+    }
+
+    // Only goes once nothing is left to resume
+    let _ = std::fs::remove_dir(input_dir.join(WORK_DIR));
+    Ok(summary)
+}
+
+// Ingest one input, returning its name. `previous` is the name it was given
+// by an earlier run, reused when it is forced through again.
+fn ingest_one(
+    input: &Path,
+    index: usize,
+    key: &InputKey,
+    previous: Option<&str>,
+    input_dir: &Path,
+    options: &IngestOptions,
+) -> Result<String> {
+    let work = input_dir.join(WORK_DIR).join(key.digest());
+    std::fs::create_dir_all(&work)?;
+
+    let audio = work.join("transcription.wav");
+    if audio.exists() {
+        println!("  converted audio found, resuming");
+    } else {
+        println!("  converting to 16kHz mono");
+        let partial = work.join("transcription.partial.wav");
+        ffmpeg(input, &partial, &["-acodec", "pcm_s16le", "-ar", "16000", "-ac", "1"])?;
+        std::fs::rename(&partial, &audio)?;
+    }
+
+    let vtt = work.join("transcript.vtt");
+    let json = work.join("transcript.json");
+    if vtt.exists() {
+        println!("  transcript found, resuming");
+    } else {
+        println!("  transcribing with {}", options.whisper.display());
+        transcribe(&audio, &work.join("transcript.partial"), options)?;
+        if options.json {
+            std::fs::rename(work.join("transcript.partial.json"), &json)?;
+        }
+        std::fs::rename(work.join("transcript.partial.vtt"), &vtt)?;
+    }
+
+    // Keywords are weighed against the recordings already ingested
+    let text = namer::transcript_text(&std::fs::read_to_string(&vtt)?);
+    let keywords = namer::suggest_name(&text, &namer::corpus_in_dir(input_dir, None), &options.keywords);
+
+    let title_path = work.join("title.txt");
+    let title = match (&options.namer, std::fs::read_to_string(&title_path)) {
+        (Some(_), Ok(title)) => title,
+        (Some(namer), Err(_)) => {
+            println!("  naming with `{}`", namer);
+            let title = run_namer(namer, &vtt)?;
+            std::fs::write(&title_path, &title)?;
+            title
+        }
+        (None, _) => keywords.clone().unwrap_or_else(|| stem(input)),
+    };
+    let keywords = keywords.unwrap_or_else(|| stem(input));
+
+    let name = unique_name(input_dir, &render_name(&options.template, input, &title, &keywords, index), previous);
+    let target = |extension: &str| input_dir.join(format!("{}.{}", name, extension));
+
+    if options.master {
+        println!("  writing master audio");
+        let partial = work.join("master.partial.wav");
+        ffmpeg(input, &partial, &["-acodec", "pcm_s24le"])?;
+        std::fs::rename(&partial, master::master_path(&target("wav")))?;
+    }
+    std::fs::rename(&audio, target("wav"))?;
+    if json.exists() {
+        std::fs::rename(&json, target("json"))?;
+    }
+    // The VTT goes last: once it exists the recording shows up in the TUI
+    std::fs::rename(&vtt, target("vtt"))?;
+
+    std::fs::remove_dir_all(&work)?;
+    Ok(name)
+}
+
+// Follow a recording renamed after ingest, so a forced rerun reuses the new name
+pub fn record_rename(input_dir: &Path, old: &str, new: &str) -> Result<()> {
+    let state_path = input_dir.join(STATE_FILE);
+    let Ok(state) = std::fs::read_to_string(&state_path) else {
+        return Ok(());
+    };
+    let mut updated = String::new();
+    for line in state.lines() {
+        match line.split_once('\t') {
+            Some((name, key)) if name == old => writeln!(updated, "{}\t{}", new, key)?,
+            _ => writeln!(updated, "{}", line)?,
+        }
+    }
+    std::fs::write(&state_path, updated)?;
+    Ok(())
+}
+
+fn ffmpeg(input: &Path, output: &Path, codec_args: &[&str]) -> Result<()> {
+    let result = Command::new("ffmpeg")
+        .arg("-i").arg(input)
+        .args(codec_args)
+        .arg(output)
+        .arg("-y")
+        .output()?;
+
+    if !result.status.success() {
+        let error = String::from_utf8_lossy(&result.stderr);
+        return Err(ParasiteError::AudioProcessing(format!("ffmpeg error: {}", error)).into());
+    }
+    Ok(())
+}
+
+// Run whisper.cpp, writing `<output_stem>.vtt` (and `.json`)
+fn transcribe(audio: &Path, output_stem: &Path, options: &IngestOptions) -> Result<()> {
+    let mut command = Command::new(&options.whisper);
+    command.arg("--model").arg(&options.model).arg("-ovtt");
+    if options.json {
+        command.arg("-oj");
+    }
+    if let Some(language) = &options.language {
+        command.args(["-l", language]);
+    }
+    let result = command
+        .args(&options.extra_args)
+        .arg("-of").arg(output_stem)
+        .arg(audio)
+        .output()
+        .map_err(|e| ParasiteError::AudioProcessing(format!("cannot run {}: {}", options.whisper.display(), e)))?;
+
+    if !result.status.success() {
+        let error = String::from_utf8_lossy(&result.stderr);
+        return Err(ParasiteError::AudioProcessing(format!("whisper error: {}", error)).into());
+    }
+    Ok(())
+}
+
+// Pipe the transcript into the namer command and take the first line it prints
+fn run_namer(namer: &str, vtt: &Path) -> Result<String> {
+    let mut child = Command::new("sh")
+        .args(["-c", namer])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&std::fs::read(vtt)?)?;
+    }
+    let result = child.wait_with_output()?;
+
+    let title = String::from_utf8_lossy(&result.stdout).lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or("").to_string();
+    if !result.status.success() || title.is_empty() {
+        let error = String::from_utf8_lossy(&result.stderr);
+        return Err(ParasiteError::AudioProcessing(format!("namer gave no title: {}", error.trim())).into());
+    }
+    Ok(title)
+}
+
+// Fill in a name template: {title} (from the namer command, else the
+// keywords), {keywords} (from the transcript, else the input's name), {stem}
+// (the input's name), {date} (its modification date) and {index} (position
+// in the batch)
+fn render_name(template: &str, input: &Path, title: &str, keywords: &str, index: usize) -> String {
+    let date = std::fs::metadata(input)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| civil_date(d.as_secs() / 86400))
+        .unwrap_or_default();
+
+    let name = template
+        .replace("{title}", title)
+        .replace("{keywords}", keywords)
+        .replace("{stem}", &stem(input))
+        .replace("{date}", &date)
+        .replace("{index}", &format!("{:03}", index));
+    sanitize(&name)
+}
+
+// Keep names to characters that are safe on every filesystem. Dots go too,
+// as they would read as extensions: `talk.master` would pass for the master
+// of `talk`.
+pub fn sanitize(name: &str) -> String {
+    let cleaned: String = name
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'')
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let collapsed = cleaned.split('_').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("_");
+    if collapsed.is_empty() { "recording".to_string() } else { collapsed }
+}
+
+// Add a numeric suffix if another recording already has the name. A name
+// the input was given before is free to reuse.
+pub fn unique_name(input_dir: &Path, name: &str, previous: Option<&str>) -> String {
+    let taken = |candidate: &str| {
+        previous != Some(candidate) && input_dir.join(format!("{}.vtt", candidate)).exists()
+    };
+    if !taken(name) {
+        return name.to_string();
+    }
+    (2..).map(|n| format!("{}_{}", name, n)).find(|candidate| !taken(candidate)).unwrap_or_default()
+}
+
+fn stem(path: &Path) -> String {
+    path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+// YYYY-MM-DD for a day count since 1970-01-01 (proleptic Gregorian)
+fn civil_date(days: u64) -> String {
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
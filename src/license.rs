@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::master;
+
+// License details for a source recording, read from a sidecar file: the
+// first non-empty line names the license (e.g. "CC-BY-4.0"), any following
+// lines give the attribution text. A `<source>.license` file applies to one
+// recording; a `.license` file in a directory applies to every recording in
+// it and below, unless a closer sidecar overrides it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLicense {
+    pub license: String,
+    pub attribution: String,
+}
+
+impl SourceLicense {
+    pub fn for_source(source: &Path) -> Option<SourceLicense> {
+        // A master shares its recording's sidecar
+        let recording = master::transcription_path(source).unwrap_or_else(|| source.to_path_buf());
+        let file_sidecar = recording.with_extension("license");
+        let dir_sidecars = source.ancestors().skip(1).map(|dir| dir.join(".license"));
+
+        std::iter::once(file_sidecar)
+            .chain(dir_sidecars)
+            .find_map(|sidecar| SourceLicense::read(&sidecar))
+    }
+
+    fn read(sidecar: &Path) -> Option<SourceLicense> {
+        let content = std::fs::read_to_string(sidecar).ok()?;
+        let mut lines = content.lines().map(str::trim).skip_while(|l| l.is_empty());
+
+        let license = lines.next()?.to_string();
+        let attribution = lines
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Some(SourceLicense { license, attribution })
+    }
+
+    fn terms(&self) -> Option<LicenseTerms> {
+        LicenseTerms::parse(&self.license)
+    }
+}
+
+// What a license allows, for the license identifiers we recognise
+#[derive(Clone, Debug, Default)]
+struct LicenseTerms {
+    id: String,           // Normalised identifier, e.g. "CC-BY-SA-4.0"
+    share_alike: bool,    // Derivatives must use the same license
+    non_commercial: bool, // No commercial use
+    no_derivatives: bool, // No remixing or sampling at all
+    internal: bool,       // Not for distribution outside the organisation
+}
+
+impl LicenseTerms {
+    fn parse(license: &str) -> Option<LicenseTerms> {
+        let id = license.trim().to_lowercase().replace([' ', '_'], "-");
+        let normalised = id.to_uppercase();
+
+        if id.starts_with("internal") {
+            return Some(LicenseTerms { id: normalised, internal: true, ..Default::default() });
+        }
+        if id.starts_with("cc0") || id == "public-domain" || id == "pd" {
+            return Some(LicenseTerms { id: normalised, ..Default::default() });
+        }
+        let elements = id.strip_prefix("cc-by").filter(|rest| rest.is_empty() || rest.starts_with('-'))?;
+        let has = |element: &str| elements.split('-').any(|token| token == element);
+        Some(LicenseTerms {
+            share_alike: has("sa"),
+            non_commercial: has("nc"),
+            no_derivatives: has("nd"),
+            internal: false,
+            id: normalised,
+        })
+    }
+}
+
+// How a set of samples is about to be used
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LicenseUse {
+    Export,  // Instrument built locally
+    Package, // Archive meant for distribution
+}
+
+// Outcome of checking a pack's licenses against each other
+#[derive(Default)]
+pub struct LicenseReport {
+    pub conflicts: Vec<String>, // Problems that should stop the export
+    pub warnings: Vec<String>,  // Problems worth pointing out
+}
+
+impl LicenseReport {
+    pub fn is_ok(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+// Check the licenses of the sources going into one pack. `sources` pairs each
+// source's display name with its license, if known.
+pub fn check(sources: &[(String, Option<SourceLicense>)], usage: LicenseUse) -> LicenseReport {
+    let mut report = LicenseReport::default();
+    let mut share_alike: BTreeMap<String, (String, bool)> = BTreeMap::new(); // License -> first source, non-commercial
+    let mut non_commercial: Option<&str> = None; // First NC source without share-alike
+    let mut any_non_commercial = false;
+    let mut internal: Option<&str> = None;
+
+    for (source, license) in sources {
+        let Some(license) = license else {
+            report.warnings.push(format!("{} has no license metadata", source));
+            continue;
+        };
+        let Some(terms) = license.terms() else {
+            report.warnings.push(format!("{}: unrecognised license '{}'", source, license.license));
+            continue;
+        };
+
+        if terms.no_derivatives {
+            report.conflicts.push(format!("{} is {}, which does not allow derivatives", source, license.license));
+        }
+        if terms.internal {
+            if usage == LicenseUse::Package {
+                report.conflicts.push(format!("{} is {} and cannot be distributed", source, license.license));
+            }
+            internal.get_or_insert(source);
+        }
+        if terms.share_alike {
+            share_alike.entry(terms.id.clone()).or_insert_with(|| (source.clone(), terms.non_commercial));
+        }
+        if terms.non_commercial && !terms.share_alike {
+            non_commercial.get_or_insert(source);
+        }
+        any_non_commercial |= terms.non_commercial;
+    }
+
+    if any_non_commercial {
+        report.warnings.push("Contains non-commercial material: the pack cannot be used commercially".to_string());
+    }
+
+    // A share-alike pack must carry exactly that license, so it can't be combined
+    // with another share-alike license or with extra restrictions
+    if share_alike.len() > 1 {
+        let names: Vec<String> = share_alike.iter().map(|(l, (s, _))| format!("{} ({})", l, s)).collect();
+        report.conflicts.push(format!("Mixes incompatible share-alike licenses: {}", names.join(", ")));
+    }
+    if let Some((license, (sa_source, sa_non_commercial))) = share_alike.iter().next() {
+        if let Some(source) = non_commercial.filter(|_| !sa_non_commercial) {
+            report.conflicts.push(format!("{} is non-commercial but {} is {}", source, sa_source, license));
+        }
+        if let Some(source) = internal {
+            report.conflicts.push(format!("{} is internal-only but {} is {}", source, sa_source, license));
+        }
+    }
+
+    report
+}
+
+// Attribution list grouping every source by license
+pub fn attribution_list(sources: &[(String, Option<SourceLicense>)]) -> String {
+    let mut by_license: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    for (source, license) in sources {
+        let (name, attribution) = match license {
+            Some(l) => (l.license.clone(), l.attribution.clone()),
+            None => ("Unknown (no license metadata)".to_string(), String::new()),
+        };
+        by_license.entry(name).or_default().insert(source.clone(), attribution);
+    }
+
+    let mut text = String::new();
+    let _ = writeln!(text, "This pack contains material under the following licenses.");
+    let _ = writeln!(text);
+    for (license, sources) in &by_license {
+        let _ = writeln!(text, "{}", license);
+        let _ = writeln!(text, "{}", "-".repeat(license.chars().count()));
+        for (source, attribution) in sources {
+            if attribution.is_empty() {
+                let _ = writeln!(text, "- {}", source);
+            } else {
+                let _ = writeln!(text, "- {}: {}", source, attribution);
+            }
+        }
+        let _ = writeln!(text);
+    }
+    text
+}
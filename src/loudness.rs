@@ -0,0 +1,391 @@
+use std::f64::consts::PI;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+use crate::audio::{AudioBuffer, Biquad};
+use crate::config::Config;
+use crate::metadata;
+use crate::ParasiteError;
+
+// EBU R128 / ITU-R BS.1770 gating
+const BLOCK: Duration = Duration::from_millis(400);
+const BLOCK_STEP: Duration = Duration::from_millis(100);
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+// Limiter timing
+const LIMITER_LOOKAHEAD: Duration = Duration::from_micros(1500);
+const LIMITER_RELEASE: Duration = Duration::from_millis(50);
+// Oversampling used to estimate true peak
+const TRUE_PEAK_OVERSAMPLING: u32 = 4;
+const TRUE_PEAK_TAPS: usize = 12; // Interpolation kernel half-width, in samples
+
+pub const DEFAULT_TRUE_PEAK_DB: f32 = -1.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum NormalizeMode {
+    /// Sample peak, in dBFS
+    Peak,
+    /// RMS level, in dBFS
+    Rms,
+    /// Integrated loudness (EBU R128), in LUFS
+    Lufs,
+}
+
+impl NormalizeMode {
+    pub fn default_target(self) -> f32 {
+        match self {
+            NormalizeMode::Peak => -1.0,
+            NormalizeMode::Rms => -18.0,
+            NormalizeMode::Lufs => -16.0,
+        }
+    }
+
+    pub fn unit(self) -> &'static str {
+        match self {
+            NormalizeMode::Peak | NormalizeMode::Rms => "dBFS",
+            NormalizeMode::Lufs => "LUFS",
+        }
+    }
+
+    // Level of `buffer` as this mode measures it
+    pub fn measure(self, buffer: &AudioBuffer) -> f32 {
+        match self {
+            NormalizeMode::Peak => to_db(buffer.peak() as f64),
+            NormalizeMode::Rms => rms_db(buffer),
+            NormalizeMode::Lufs => integrated_loudness(buffer),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct NormalizeOptions {
+    pub mode: NormalizeMode,
+    pub target: f32,       // Target level in the mode's unit
+    pub true_peak: f32,    // Ceiling (dBTP) enforced by the limiter after gain
+}
+
+impl NormalizeOptions {
+    pub fn new(mode: NormalizeMode) -> NormalizeOptions {
+        NormalizeOptions {
+            mode,
+            target: mode.default_target(),
+            true_peak: DEFAULT_TRUE_PEAK_DB,
+        }
+    }
+
+    // Settings from the `[normalize]` section: `mode`, `target` and
+    // `true_peak`. Normalization is off unless a mode is given.
+    pub fn from_config(config: &Config) -> Result<Option<NormalizeOptions>> {
+        let Some(name) = config.get("normalize.mode") else {
+            return Ok(None);
+        };
+        let mode = NormalizeMode::from_str(name, true)
+            .map_err(|_| ParasiteError::Config(format!("invalid value for normalize.mode: '{}'", name)))?;
+
+        let mut options = NormalizeOptions::new(mode);
+        if let Some(target) = config.get_f32("normalize.target")? {
+            options.target = target;
+        }
+        if let Some(true_peak) = config.get_f32("normalize.true_peak")? {
+            options.true_peak = true_peak;
+        }
+        Ok(Some(options))
+    }
+}
+
+// Bring `buffer` to the target level, then limit it to the true-peak ceiling.
+// Returns the level measured before processing.
+pub fn normalize(buffer: &mut AudioBuffer, options: &NormalizeOptions) -> f32 {
+    let measured = options.mode.measure(buffer);
+    if !measured.is_finite() {
+        return measured; // Digital silence
+    }
+
+    let gain = from_db(options.target - measured) as f32;
+    for s in &mut buffer.samples {
+        *s *= gain;
+    }
+    limit(buffer, options.true_peak);
+    measured
+}
+
+fn to_db(value: f64) -> f32 {
+    (20.0 * value.log10()) as f32
+}
+
+fn from_db(db: f32) -> f64 {
+    10f64.powf(db as f64 / 20.0)
+}
+
+fn rms_db(buffer: &AudioBuffer) -> f32 {
+    if buffer.samples.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    let mean_square = buffer.samples.iter().map(|&s| (s as f64).powi(2)).sum::<f64>() / buffer.samples.len() as f64;
+    (10.0 * mean_square.log10()) as f32
+}
+
+// Peak of the signal reconstructed between samples, interpolating
+// TRUE_PEAK_OVERSAMPLING - 1 points between each pair with a windowed sinc
+pub fn true_peak_db(buffer: &AudioBuffer) -> f32 {
+    let channels = buffer.channels as usize;
+    let frames = buffer.frames() as isize;
+    let taps = TRUE_PEAK_TAPS as isize;
+
+    // One kernel per fractional position between samples
+    let kernels: Vec<Vec<f64>> = (1..TRUE_PEAK_OVERSAMPLING)
+        .map(|phase| {
+            let fraction = phase as f64 / TRUE_PEAK_OVERSAMPLING as f64;
+            (-taps + 1..=taps)
+                .map(|k| {
+                    let x = k as f64 - fraction;
+                    let sinc = (PI * x).sin() / (PI * x);
+                    let window = 0.5 + 0.5 * (PI * x / taps as f64).cos();
+                    sinc * window
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut peak = buffer.peak() as f64;
+    for c in 0..channels {
+        let sample = |i: isize| {
+            if i < 0 || i >= frames { 0.0 } else { buffer.samples[i as usize * channels + c] as f64 }
+        };
+        for i in 0..frames {
+            for kernel in &kernels {
+                let value: f64 = kernel.iter()
+                    .zip(-taps + 1..=taps)
+                    .map(|(w, k)| w * sample(i + k))
+                    .sum();
+                peak = peak.max(value.abs());
+            }
+        }
+    }
+    to_db(peak)
+}
+
+// Integrated loudness per ITU-R BS.1770-4: K-weighted mean square over 400ms
+// blocks, gated at -70 LUFS and then 10 LU below the ungated mean. Samples
+// shorter than one block are measured as a single block.
+pub fn integrated_loudness(buffer: &AudioBuffer) -> f32 {
+    let channels = buffer.channels as usize;
+    let frames = buffer.frames();
+    if frames == 0 {
+        return f32::NEG_INFINITY;
+    }
+
+    // K-weighted energy per frame, summed across channels
+    let mut energy = vec![0.0f64; frames];
+    for c in 0..channels {
+        let mut shelf = k_weighting_shelf(buffer.sample_rate);
+        let mut high_pass = k_weighting_high_pass(buffer.sample_rate);
+        for (i, e) in energy.iter_mut().enumerate() {
+            let weighted = high_pass.process(shelf.process(buffer.samples[i * channels + c] as f64));
+            *e += weighted * weighted;
+        }
+    }
+
+    let to_frames = |d: Duration| (d.as_secs_f64() * buffer.sample_rate as f64).round() as usize;
+    let block = to_frames(BLOCK).min(frames);
+    let step = to_frames(BLOCK_STEP).max(1);
+
+    let blocks: Vec<f64> = (0..=frames - block)
+        .step_by(step)
+        .map(|start| energy[start..start + block].iter().sum::<f64>() / block as f64)
+        .collect();
+
+    let loudness = |mean_square: f64| -0.691 + 10.0 * mean_square.log10();
+    let gated_mean = |threshold: f64| {
+        let kept: Vec<f64> = blocks.iter().copied().filter(|&b| loudness(b) > threshold).collect();
+        (!kept.is_empty()).then(|| kept.iter().sum::<f64>() / kept.len() as f64)
+    };
+
+    let Some(ungated) = gated_mean(ABSOLUTE_GATE_LUFS) else {
+        return f32::NEG_INFINITY;
+    };
+    let relative_gate = loudness(ungated) + RELATIVE_GATE_LU;
+    let integrated = gated_mean(relative_gate.max(ABSOLUTE_GATE_LUFS)).unwrap_or(ungated);
+    loudness(integrated) as f32
+}
+
+// Lookahead peak limiter holding the signal under `ceiling_db`, followed by a
+// static trim if inter-sample peaks still exceed it
+pub fn limit(buffer: &mut AudioBuffer, ceiling_db: f32) {
+    let ceiling = from_db(ceiling_db) as f32;
+    let channels = buffer.channels as usize;
+    let frames = buffer.frames();
+    let to_frames = |d: Duration| ((d.as_secs_f64() * buffer.sample_rate as f64).round() as usize).max(1);
+    let lookahead = to_frames(LIMITER_LOOKAHEAD);
+    let release = 1.0 / to_frames(LIMITER_RELEASE) as f32;
+
+    // Gain each frame needs on its own
+    let required: Vec<f32> = (0..frames)
+        .map(|i| {
+            let peak = (0..channels).map(|c| buffer.samples[i * channels + c].abs()).fold(0.0, f32::max);
+            if peak > ceiling { ceiling / peak } else { 1.0 }
+        })
+        .collect();
+
+    if required.iter().any(|&g| g < 1.0) {
+        // Ramp down over the lookahead so the gain is reached by the peak
+        let mut gain = vec![1.0f32; frames];
+        for (i, &g) in required.iter().enumerate().filter(|(_, &g)| g < 1.0) {
+            for k in 0..lookahead.min(i + 1) {
+                let ramp = g + (1.0 - g) * k as f32 / lookahead as f32;
+                gain[i - k] = gain[i - k].min(ramp);
+            }
+        }
+
+        // Recover gradually after each peak
+        let mut current = 1.0f32;
+        for (i, g) in gain.iter().enumerate() {
+            current = g.min(current + (1.0 - current) * release);
+            for c in 0..channels {
+                buffer.samples[i * channels + c] *= current;
+            }
+        }
+    }
+
+    let true_peak = true_peak_db(buffer);
+    if true_peak > ceiling_db {
+        let trim = from_db(ceiling_db - true_peak) as f32;
+        for s in &mut buffer.samples {
+            *s *= trim;
+        }
+    }
+}
+
+// BS.1770 stage 1: +4 dB shelf modelling the head's acoustic effect
+fn k_weighting_shelf(sample_rate: u32) -> Biquad {
+    let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * f0 / sample_rate as f64).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    Biquad::new(
+        [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    )
+}
+
+// BS.1770 stage 2: RLB high-pass
+fn k_weighting_high_pass(sample_rate: u32) -> Biquad {
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * f0 / sample_rate as f64).tan();
+    let a0 = 1.0 + k / q + k * k;
+    Biquad::new([1.0, -2.0, 1.0], [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0])
+}
+
+// Levels of one file before and after batch normalization
+pub struct NormalizeReport {
+    pub path: PathBuf,
+    pub before: f32,
+    pub after: f32,
+}
+
+// Normalize WAVs in place, keeping their metadata. With `match_loudness` the
+// target is the median level of the files, so the pack ends up even with the
+// least overall change.
+pub fn normalize_files(paths: &[PathBuf], options: &NormalizeOptions, match_loudness: bool) -> Result<Vec<NormalizeReport>> {
+    let mut buffers = Vec::new();
+    for path in paths {
+        buffers.push(AudioBuffer::read_wav(path)?);
+    }
+
+    let mut options = *options;
+    if match_loudness {
+        let mut levels: Vec<f32> = buffers.iter()
+            .map(|b| options.mode.measure(b))
+            .filter(|l| l.is_finite())
+            .collect();
+        levels.sort_by(|a, b| a.total_cmp(b));
+        if let Some(&median) = levels.get(levels.len() / 2) {
+            options.target = median;
+        }
+    }
+
+    let mut reports = Vec::new();
+    for (path, mut buffer) in paths.iter().zip(buffers) {
+        let before = normalize(&mut buffer, &options);
+        metadata::replace_audio(path, &buffer)?;
+        reports.push(NormalizeReport {
+            path: path.clone(),
+            before,
+            after: options.mode.measure(&buffer),
+        });
+    }
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48000;
+
+    // Mono sine of the given amplitude, frequency and phase
+    fn sine(amplitude: f32, frequency: f64, phase: f64, seconds: f64) -> AudioBuffer {
+        let samples = (0..(seconds * RATE as f64) as usize)
+            .map(|i| amplitude * (2.0 * PI * frequency * i as f64 / RATE as f64 + phase).sin() as f32)
+            .collect();
+        AudioBuffer { sample_rate: RATE, channels: 1, samples }
+    }
+
+    #[test]
+    fn a_calibration_sine_reads_minus_23_lufs() {
+        // BS.1770 calibration: 997Hz at -20dBFS in one channel is -23.01 LUFS
+        let loudness = integrated_loudness(&sine(0.1, 997.0, 0.0, 3.0));
+        assert!((loudness + 23.01).abs() < 0.1, "{}", loudness);
+    }
+
+    #[test]
+    fn the_relative_gate_ignores_quiet_passages() {
+        // Two seconds at -20dBFS then two at -60dBFS. The quiet blocks are
+        // more than 10 LU down and dropped; the 17 loud blocks and the three
+        // straddling the change (3/4, 1/2 and 1/4 loud) stay, giving
+        // -23.01 + 10log(18.5 / 20) = -23.35 rather than the ungated -26
+        let mut buffer = sine(0.1, 997.0, 0.0, 2.0);
+        buffer.append(&sine(0.001, 997.0, 0.0, 2.0));
+        let loudness = integrated_loudness(&buffer);
+        assert!((loudness + 23.35).abs() < 0.05, "{}", loudness);
+    }
+
+    #[test]
+    fn silence_and_empty_buffers_have_no_loudness() {
+        let silence = AudioBuffer { sample_rate: RATE, channels: 1, samples: vec![0.0; RATE as usize] };
+        assert_eq!(integrated_loudness(&silence), f32::NEG_INFINITY);
+        let empty = AudioBuffer { sample_rate: RATE, channels: 1, samples: Vec::new() };
+        assert_eq!(integrated_loudness(&empty), f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn true_peak_finds_the_peak_between_samples() {
+        // A quarter-rate sine shifted 45 degrees peaks halfway between
+        // samples that only reach -3dBFS
+        let buffer = sine(1.0, RATE as f64 / 4.0, PI / 4.0, 0.1);
+        assert!((to_db(buffer.peak() as f64) + 3.01).abs() < 0.01);
+        let true_peak = true_peak_db(&buffer);
+        assert!(true_peak.abs() < 0.3, "{}", true_peak);
+    }
+
+    #[test]
+    fn the_limiter_holds_true_peaks_under_the_ceiling() {
+        let mut buffer = sine(1.0, RATE as f64 / 4.0, PI / 4.0, 0.1);
+        limit(&mut buffer, -1.0);
+        let true_peak = true_peak_db(&buffer);
+        assert!(true_peak <= -1.0 + 1e-3, "{}", true_peak);
+    }
+
+    #[test]
+    fn the_limiter_leaves_quiet_audio_alone() {
+        let mut buffer = sine(0.5, 997.0, 0.0, 0.5);
+        let original = buffer.samples.clone();
+        limit(&mut buffer, -1.0);
+        assert_eq!(buffer.samples, original);
+    }
+}
@@ -1,34 +1,247 @@
+use std::collections::HashMap;
 use std::io;
 use std::time::Duration;
 use std::path::PathBuf;
 use std::process::Command;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
+mod audio;
+mod config;
+mod export;
+mod fade;
+mod ingest;
+mod license;
+mod loudness;
+mod master;
+mod metadata;
+mod namer;
+mod output;
+mod package;
+mod padding;
+mod playback;
+mod processing;
+mod snap;
+mod spectrogram;
+mod supercut;
+mod watch;
+mod waveform;
+
+use audio::AudioBuffer;
+use export::chain::ChainOptions;
+use export::{ExportFormat, ExportSample, KeyLayout, KeyMap};
+use fade::{FadeCurve, FadeOptions, FadeOverride};
+use ingest::IngestOptions;
+use license::{LicenseUse, SourceLicense};
+use loudness::{NormalizeMode, NormalizeOptions};
+use master::MasterAudio;
+use metadata::SampleMetadata;
+use namer::NamerOptions;
+use output::{OutputFormat, OutputVariant};
+use package::{ArchiveFormat, PackageOptions};
+use padding::{Bounds, Padding};
+use playback::{Player, PreviewBackend};
+use processing::{ChainChoice, ProcessingChain};
+use snap::SnapOptions;
+use spectrogram::Spectrogram;
+use supercut::{Segment, SupercutOptions};
+use watch::SourceWatcher;
+use waveform::Waveform;
+
 // Time adjustment constants in milliseconds
 const NORMAL_TIME_ADJUST: i64 = 100;
 const FINE_TIME_ADJUST: i64 = 25;
+// Shortest a line can be adjusted to, in milliseconds
+const MIN_LINE_MS: i64 = 10;
+// Rows given to the waveform pane, borders included
+const WAVEFORM_HEIGHT: u16 = 10;
+// Rows given to the spectrogram pane, borders included
+const SPECTROGRAM_HEIGHT: u16 = 12;
 
 /// Parasite: Vocal Sample Pack Creator
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     /// Directory containing VTT and WAV files
-    #[arg(short, long, default_value = "data")]
+    #[arg(short, long, default_value = "data", global = true)]
     input_dir: String,
 
     /// Directory for saving extracted samples
-    #[arg(short, long, default_value = "output")]
+    #[arg(short, long, default_value = "output", global = true)]
     output_dir: String,
+
+    /// Audio kept before each cue when extracting and previewing (milliseconds)
+    #[arg(long, global = true)]
+    pre_roll_ms: Option<u64>,
+
+    /// Audio kept after each cue when extracting and previewing (milliseconds)
+    #[arg(long, global = true)]
+    post_roll_ms: Option<u64>,
+
+    /// Keep padding within the file and clear of neighbouring cues
+    #[arg(long, global = true)]
+    clamp_padding: bool,
+
+    /// Snap unadjusted cues to the speech onset and offset when extracting
+    #[arg(long, global = true)]
+    snap: bool,
+
+    /// Fade-in applied to extracted samples (milliseconds)
+    #[arg(long, global = true)]
+    fade_in_ms: Option<u64>,
+
+    /// Fade-out applied to extracted samples (milliseconds)
+    #[arg(long, global = true)]
+    fade_out_ms: Option<u64>,
+
+    /// Shape of the fades
+    #[arg(long, value_enum, global = true)]
+    fade_curve: Option<FadeCurve>,
+
+    /// Move extraction boundaries to the nearest zero crossing
+    #[arg(long, global = true)]
+    zero_crossing: bool,
+
+    /// Normalize extracted samples by peak, RMS or integrated loudness
+    #[arg(long, value_enum, global = true)]
+    normalize: Option<NormalizeMode>,
+
+    /// Normalization target (dBFS for peak and RMS, LUFS for loudness)
+    #[arg(long, global = true, allow_negative_numbers = true)]
+    normalize_target: Option<f32>,
+
+    /// True-peak ceiling enforced after normalizing (dBTP)
+    #[arg(long, global = true, allow_negative_numbers = true)]
+    true_peak: Option<f32>,
+
+    /// Processing preset applied to extracted samples and previews ("off" for none)
+    #[arg(long, global = true)]
+    process: Option<String>,
+
+    /// Output format of extracted samples, e.g. wav:48k:24:stereo or mp3 (repeat for several variants)
+    #[arg(long, global = true, value_name = "SPEC", value_parser = OutputVariant::parse)]
+    output_format: Vec<OutputVariant>,
+
+    /// Where previews play: auto, native, pipe[:command], null or file:path
+    #[arg(long, global = true, value_name = "BACKEND", value_parser = PreviewBackend::parse)]
+    preview_backend: Option<PreviewBackend>,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Build a sampler instrument from the samples in the output directory
+    Export {
+        /// Instrument format to write
+        #[arg(value_enum)]
+        format: ExportFormat,
+
+        /// How samples are laid out across keys
+        #[arg(short, long, value_enum, default_value = "chromatic")]
+        layout: KeyLayout,
+
+        /// Lowest MIDI note to map samples onto
+        #[arg(short, long, default_value_t = export::DEFAULT_BASE_KEY)]
+        base_key: u8,
+
+        /// Instrument name (defaults to the output directory name)
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Silence between slices in chain exports (milliseconds)
+        #[arg(long, default_value_t = 0)]
+        gap_ms: u64,
+
+        /// Peak-normalize each slice in chain exports
+        #[arg(long)]
+        normalize: bool,
+
+        /// Export even if the sources' licenses conflict
+        #[arg(long)]
+        allow_license_conflicts: bool,
+    },
+    /// Render every match for a query into one WAV with VTT/SRT subtitles
+    Supercut {
+        /// Search query (all words must match, as in the TUI)
+        #[arg(required = true)]
+        query: Vec<String>,
+
+        /// Silence between segments (milliseconds)
+        #[arg(long, default_value_t = supercut::DEFAULT_GAP_MS)]
+        gap_ms: u64,
+
+        /// Fade at each segment edge, overlapping neighbours (milliseconds)
+        #[arg(long, default_value_t = supercut::DEFAULT_CROSSFADE_MS)]
+        crossfade_ms: u64,
+    },
+    /// Normalize every sample in the output directory in place
+    Normalize {
+        /// What to measure and normalize (defaults to the config's normalize.mode, else lufs)
+        #[arg(value_enum)]
+        mode: Option<NormalizeMode>,
+
+        /// Target level (defaults to -1 dBFS peak, -18 dBFS RMS or -16 LUFS)
+        #[arg(short, long, allow_negative_numbers = true)]
+        target: Option<f32>,
+
+        /// Bring every sample to the pack's median level instead of a fixed target
+        #[arg(short, long = "match")]
+        match_loudness: bool,
+    },
+    /// Convert and transcribe recordings into the input directory
+    Ingest {
+        /// Audio or video files to ingest
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Name for each recording, using {title}, {keywords}, {stem}, {date} and {index}
+        #[arg(short, long)]
+        template: Option<String>,
+
+        /// Ingest files again even if an earlier run finished them
+        #[arg(long)]
+        force: bool,
+    },
+    /// Bundle the output directory into distributable archives
+    Package {
+        /// Pack name (defaults to the output directory name)
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Instrument format to include; repeat for one archive per format
+        #[arg(short, long, value_enum)]
+        variant: Vec<ExportFormat>,
+
+        /// Archive type to write
+        #[arg(short, long, value_enum, default_value = "zip")]
+        archive: ArchiveFormat,
+
+        /// How samples are laid out across keys in instrument variants
+        #[arg(short, long, value_enum, default_value = "chromatic")]
+        layout: KeyLayout,
+
+        /// Lowest MIDI note to map samples onto in instrument variants
+        #[arg(short, long, default_value_t = export::DEFAULT_BASE_KEY)]
+        base_key: u8,
+
+        /// Package even if the sources' licenses conflict
+        #[arg(long)]
+        allow_license_conflicts: bool,
+    },
 }
+
 use ratatui::{prelude::*, widgets::*};
 use ratatui::widgets::{Row, Cell, Table, TableState};
+use ratatui::layout::Position;
+use ratatui::widgets::canvas::{Canvas, Line as CanvasLine};
 use walkdir::WalkDir;
 use thiserror::Error;
 
@@ -39,6 +252,12 @@ enum ParasiteError {
     
     #[error("Audio processing error: {0}")]
     AudioProcessing(String),
+    
+    #[error("License error: {0}")]
+    License(String),
+    
+    #[error("Config error: {0}")]
+    Config(String),
 }
 
 
@@ -53,6 +272,100 @@ struct App {
     context_lines: usize,               // Number of context lines to include above/below matches
     input_dir: String,                  // Directory containing VTT and WAV files
     output_dir: String,                 // Directory for saving extracted samples
+    marked: Vec<CueKey>,                // Cues marked for instrument export
+    export_format: ExportFormat,        // Format used when exporting marked lines
+    export_layout: KeyLayout,           // Key layout used when exporting marked lines
+    licenses: HashMap<PathBuf, SourceLicense>, // License of each source, keyed by VTT path
+    file_lengths: HashMap<PathBuf, Duration>,  // Length of each source WAV, keyed by VTT path
+    masters: HashMap<PathBuf, MasterAudio>,    // High-quality audio extracted from, keyed by VTT path
+    preview_master: bool,               // Preview from the master rather than the transcription audio
+    pending_rename: Option<(PathBuf, String)>, // Source VTT and suggested name awaiting confirmation
+    player: Option<Player>,             // Plays previews; None when no backend could be opened
+    preview: Option<Preview>,           // What the player's clip was cut from
+    looping: bool,                      // Loop previews until stopped
+    show_waveform: bool,                // Show the waveform pane
+    waveform: Option<Waveform>,         // Audio around the selected line, once loaded
+    show_spectrogram: bool,             // Show the spectrogram pane
+    spectrogram: Option<Spectrogram>,   // Spectrum of the same stretch, once analysed
+    drag: Option<Drag>,                 // Boundary being dragged with the mouse
+    settings: Settings,                 // Extraction settings from config and flags
+}
+
+// Extraction settings, from the config files with command line overrides
+#[derive(Clone, Default)]
+struct Settings {
+    padding: Padding,       // Pre/post-roll applied when extracting and previewing
+    snap: SnapOptions,      // Speech onset/offset detection
+    snap_on_extract: bool,  // Snap unadjusted lines before extracting
+    fades: FadeOptions,     // Default fades and zero-crossing snapping
+    normalize: Option<NormalizeOptions>, // Level applied to each extracted sample
+    presets: Vec<ProcessingChain>,       // Processing chains available to pick from
+    process: Option<ProcessingChain>,    // Chain applied unless a sample picks another
+    outputs: Vec<OutputVariant>,         // Files written per extraction; the first is the main one
+    namer: NamerOptions,                 // Keyword naming for renaming sources
+    preview: PreviewBackend,             // Where previews play
+}
+
+impl Settings {
+    fn load(args: &Args) -> Result<Settings> {
+        let config = config::Config::load(&args.input_dir)?;
+        let mut settings = Settings {
+            padding: Padding::from_config(&config)?,
+            snap: SnapOptions::from_config(&config)?,
+            snap_on_extract: config.get_bool("snap.on_extract")?.unwrap_or(false),
+            fades: FadeOptions::from_config(&config)?,
+            normalize: NormalizeOptions::from_config(&config)?,
+            presets: processing::presets(&config)?,
+            process: None,
+            outputs: output::variants_from_config(&config)?,
+            namer: NamerOptions::from_config(&config)?,
+            preview: PreviewBackend::from_config(&config)?,
+        };
+        
+        if let Some(ms) = args.pre_roll_ms {
+            settings.padding.pre = Duration::from_millis(ms);
+        }
+        if let Some(ms) = args.post_roll_ms {
+            settings.padding.post = Duration::from_millis(ms);
+        }
+        settings.padding.clamp |= args.clamp_padding;
+        settings.snap_on_extract |= args.snap;
+        if let Some(ms) = args.fade_in_ms {
+            settings.fades.fade_in = Duration::from_millis(ms);
+        }
+        if let Some(ms) = args.fade_out_ms {
+            settings.fades.fade_out = Duration::from_millis(ms);
+        }
+        if let Some(curve) = args.fade_curve {
+            settings.fades.curve = curve;
+        }
+        settings.fades.zero_crossing |= args.zero_crossing;
+        if let Some(mode) = args.normalize {
+            settings.normalize = Some(NormalizeOptions::new(mode));
+        }
+        if let Some(normalize) = &mut settings.normalize {
+            if let Some(target) = args.normalize_target {
+                normalize.target = target;
+            }
+            if let Some(true_peak) = args.true_peak {
+                normalize.true_peak = true_peak;
+            }
+        }
+        if let Some(name) = args.process.as_deref().or(config.get("process.preset")) {
+            settings.process = processing::find(&settings.presets, name)?;
+        }
+        if !args.output_format.is_empty() {
+            settings.outputs = args.output_format.clone();
+        }
+        if settings.outputs.is_empty() {
+            settings.outputs.push(OutputVariant::default());
+        }
+        settings.outputs.dedup();
+        if let Some(backend) = &args.preview_backend {
+            settings.preview = backend.clone();
+        }
+        Ok(settings)
+    }
 }
 
 #[derive(Clone)]
@@ -75,15 +388,86 @@ struct DisplayLine {
     is_match: bool,       // Whether this is a match (true) or context (false)
     original_start: Duration, // Original start time (for reference)
     original_end: Duration,   // Original end time (for reference)
+    fades: FadeOverride,      // Per-sample changes to the default fades
+    processing: ChainChoice,  // Per-sample choice of processing chain
+}
+
+// Identifies a cue regardless of timing adjustments: its source and its
+// timing in the transcript
+#[derive(Clone, PartialEq)]
+struct CueKey {
+    file_path: PathBuf,
+    start: Duration,
+    end: Duration,
+}
+
+impl DisplayLine {
+    fn cue_key(&self) -> CueKey {
+        CueKey { file_path: self.file_path.clone(), start: self.original_start, end: self.original_end }
+    }
+    
+    // Whether two lines refer to the same cue, regardless of timing adjustments
+    fn same_cue(&self, other: &DisplayLine) -> bool {
+        self.cue_key() == other.cue_key()
+    }
+    
+    // Whether the timing has been changed from the transcript's
+    fn is_adjusted(&self) -> bool {
+        self.start_time != self.original_start || self.end_time != self.original_end
+    }
+}
+
+// Everything needed to cut and process one sample
+struct Extraction {
+    source: PathBuf,                     // Source WAV
+    start: Duration,                     // Final range within the source
+    end: Duration,
+    chain: Option<ProcessingChain>,      // Processing applied between slicing and writing
+    fades: FadeOptions,
+    normalize: Option<NormalizeOptions>,
+}
+
+// The line a preview clip was cut from, as it was when the preview started
+struct Preview {
+    line: DisplayLine,
+    start: Duration, // Transcript time of the clip's first sample
+}
+
+// A boundary of the selected line being dragged in the waveform or
+// spectrogram, with the view frozen as it was when the drag began so the
+// marker stays under the pointer
+#[derive(Clone, Copy)]
+struct Drag {
+    start: bool, // Dragging the start rather than the end
+    view: (Duration, Duration),
+}
+
+impl Extraction {
+    fn has_processing(&self) -> bool {
+        self.chain.is_some() || self.fades.has_fades() || self.normalize.is_some()
+    }
+    
+    // Run the chain, then fade the edges so the cut doesn't click, then bring it to level
+    fn process(&self, buffer: &mut AudioBuffer) {
+        if let Some(chain) = &self.chain {
+            chain.apply(buffer);
+        }
+        self.fades.apply_to(buffer);
+        if let Some(normalize) = &self.normalize {
+            loudness::normalize(buffer, normalize);
+        }
+    }
 }
 
 impl App {
     fn new(input_dir: String, output_dir: String) -> Result<App> {
         // Load VTT files from input directory
+        // Hidden folders hold unfinished ingests
         let vtt_files = WalkDir::new(&input_dir)
             .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
             .filter_map(|e| e.ok())
-            .filter(|e| e.path().extension().map_or(false, |ext| ext == "vtt"))
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "vtt"))
             .map(|e| e.path().to_path_buf())
             .collect::<Vec<_>>();
         
@@ -98,8 +482,34 @@ impl App {
             context_lines: 0, // Start with no context lines
             input_dir,
             output_dir,
+            marked: Vec::new(),
+            export_format: ExportFormat::Sfz,
+            export_layout: KeyLayout::Chromatic,
+            licenses: HashMap::new(),
+            file_lengths: HashMap::new(),
+            masters: HashMap::new(),
+            preview_master: true,
+            pending_rename: None,
+            player: None,
+            preview: None,
+            looping: false,
+            show_waveform: true,
+            waveform: None,
+            show_spectrogram: false,
+            spectrogram: None,
+            drag: None,
+            settings: Settings::default(),
         };
         
+        // Look up each recording's license, length and master once; new
+        // pairs are aligned here
+        let mut unaligned = Vec::new();
+        for vtt in app.vtt_files.clone() {
+            if let Some(error) = app.load_source(&vtt) {
+                unaligned.push(error);
+            }
+        }
+        
         app.load_all_results()?;
         app.filter_results();
         
@@ -108,6 +518,13 @@ impl App {
                                     app.all_results.len(), 
                                     app.input_dir,
                                     app.output_dir);
+        if !app.masters.is_empty() {
+            app.status_message.push_str(&format!(" Extracting from {} master(s).", app.masters.len()));
+        }
+        if let Some(error) = unaligned.first() {
+            // Recordings whose master can't be aligned fall back to the transcription audio
+            app.status_message.push_str(&format!(" {} master(s) not used: {}", unaligned.len(), error));
+        }
         
         Ok(app)
     }
@@ -134,7 +551,7 @@ impl App {
                 let end_ms = line.end_time.as_millis() as i64;
                 
                 // If end time is too close or less than new start time, don't allow the adjustment
-                if end_ms <= new_ms + 10 {
+                if end_ms < new_ms + MIN_LINE_MS {
                     self.status_message = "Cannot adjust: Start time would exceed end time".to_string();
                     return;
                 }
@@ -177,20 +594,12 @@ impl App {
                 let current_ms = line.end_time.as_millis() as i64;
                 
                 // For end time, we need to determine the maximum duration
-                // Get the next segment's start time as a limit, if available
-                let max_end_ms = if idx < self.flat_results.len() - 1 {
-                    // If there's a next segment, use its start time as the maximum
-                    self.flat_results[idx + 1].start_time.as_millis() as i64
-                } else {
-                    // If there's no next segment, use a reasonable maximum
-                    // (current time + 30 seconds should be enough for most use cases)
-                    current_ms + 30_000
-                };
+                let max_end_ms = self.max_end_ms(idx);
                 
                 // If trying to decrease below start time + minimum gap, prevent it
                 let start_ms = line.start_time.as_millis() as i64;
                 
-                if current_ms + delta_ms < start_ms + 10 {
+                if current_ms + delta_ms < start_ms + MIN_LINE_MS {
                     self.status_message = "Cannot adjust: End time would precede start time".to_string();
                     return;
                 }
@@ -203,7 +612,7 @@ impl App {
                 }
                 
                 // Add the delta and ensure we stay within bounds
-                let new_ms = std::cmp::max(start_ms + 10, 
+                let new_ms = std::cmp::max(start_ms + MIN_LINE_MS, 
                              std::cmp::min(max_end_ms, current_ms + delta_ms));
                 
                 // Create new duration
@@ -233,81 +642,109 @@ impl App {
         }
     }
     
+    // Latest the end of line `idx` can be moved to, in milliseconds
+    fn max_end_ms(&self, idx: usize) -> i64 {
+        match self.flat_results.get(idx + 1) {
+            // If there's a next segment, use its start time as the maximum
+            Some(next) => next.start_time.as_millis() as i64,
+            // If there's no next segment, use a reasonable maximum
+            // (current time + 30 seconds should be enough for most use cases)
+            None => self.flat_results.get(idx).map_or(0, |line| line.end_time.as_millis() as i64) + 30_000,
+        }
+    }
+    
+    // `start`..`end` moved within the limits the adjustment keys keep to:
+    // the end no later than `max_end_ms` and the line at least `MIN_LINE_MS`
+    // long
+    fn clamp_range(&self, idx: usize, start: Duration, end: Duration) -> (Duration, Duration) {
+        let end_ms = (end.as_millis() as i64).min(self.max_end_ms(idx)).max(MIN_LINE_MS);
+        let start_ms = (start.as_millis() as i64).min(end_ms - MIN_LINE_MS).max(0);
+        (Duration::from_millis(start_ms as u64), Duration::from_millis(end_ms as u64))
+    }
+    
     fn load_all_results(&mut self) -> Result<()> {
         self.all_results.clear();
         
         for file_path in &self.vtt_files {
-            let content = std::fs::read_to_string(file_path)?;
-            
-            // Basic VTT parsing
-            let lines: Vec<&str> = content.lines().collect();
-            
-            for i in 0..lines.len() {
-                // Skip WEBVTT header and timing lines
-                if i > 0 && !lines[i].contains("-->") && !lines[i].trim().is_empty() {
-                    let text = lines[i].trim();
-                    
-                    // Find timing info from previous line
-                    if i > 0 {
-                        if let Some(timing_line) = lines[0..i].iter().rev().find(|line| line.contains("-->")) {
-                            if let Some((start_time, end_time)) = parse_time_range(timing_line) {
-                                // Collect context lines (text lines, not timing lines)
-                                let mut context_before = Vec::new();
-                                let mut context_after = Vec::new();
-                                
-                                // Look for context before (up to MAX_CONTEXT_LINES)
-                                let mut before_idx = i as i32 - 1;
-                                while before_idx >= 0 && context_before.len() < 5 {
-                                    let before_line = lines[before_idx as usize].trim();
-                                    if !before_line.contains("-->") && !before_line.is_empty() {
-                                        // Find timing for this context line
-                                        if let Some(context_timing) = lines[0..before_idx as usize]
-                                            .iter()
-                                            .rev()
-                                            .find(|line| line.contains("-->")) {
-                                            if let Some((ctx_start, ctx_end)) = parse_time_range(context_timing) {
-                                                context_before.insert(0, (before_line.to_string(), ctx_start, ctx_end));
-                                            }
+            self.all_results.extend(Self::parse_vtt(file_path)?);
+        }
+        
+        self.status_message = format!("Loaded {} samples", self.all_results.len());
+        Ok(())
+    }
+    
+    // Read a VTT file's cues, each with up to five lines of context either side
+    fn parse_vtt(file_path: &PathBuf) -> Result<Vec<SearchResult>> {
+        let mut results = Vec::new();
+        let content = std::fs::read_to_string(file_path)?;
+        
+        // Basic VTT parsing
+        let lines: Vec<&str> = content.lines().collect();
+        
+        for i in 0..lines.len() {
+            // Skip WEBVTT header and timing lines
+            if i > 0 && !lines[i].contains("-->") && !lines[i].trim().is_empty() {
+                let text = lines[i].trim();
+                
+                // Find timing info from previous line
+                if i > 0 {
+                    if let Some(timing_line) = lines[0..i].iter().rev().find(|line| line.contains("-->")) {
+                        if let Some((start_time, end_time)) = parse_time_range(timing_line) {
+                            // Collect context lines (text lines, not timing lines)
+                            let mut context_before = Vec::new();
+                            let mut context_after = Vec::new();
+                            
+                            // Look for context before (up to MAX_CONTEXT_LINES)
+                            let mut before_idx = i as i32 - 1;
+                            while before_idx >= 0 && context_before.len() < 5 {
+                                let before_line = lines[before_idx as usize].trim();
+                                if !before_line.contains("-->") && !before_line.is_empty() {
+                                    // Find timing for this context line
+                                    if let Some(context_timing) = lines[0..before_idx as usize]
+                                        .iter()
+                                        .rev()
+                                        .find(|line| line.contains("-->")) {
+                                        if let Some((ctx_start, ctx_end)) = parse_time_range(context_timing) {
+                                            context_before.insert(0, (before_line.to_string(), ctx_start, ctx_end));
                                         }
                                     }
-                                    before_idx -= 1;
                                 }
-                                
-                                // Look for context after (up to MAX_CONTEXT_LINES)
-                                let mut after_idx = i + 1;
-                                while after_idx < lines.len() && context_after.len() < 5 {
-                                    let after_line = lines[after_idx].trim();
-                                    if !after_line.contains("-->") && !after_line.is_empty() {
-                                        // Find timing for this context line
-                                        if let Some(context_timing) = lines[0..after_idx]
-                                            .iter()
-                                            .rev()
-                                            .find(|line| line.contains("-->")) {
-                                            if let Some((ctx_start, ctx_end)) = parse_time_range(context_timing) {
-                                                context_after.push((after_line.to_string(), ctx_start, ctx_end));
-                                            }
+                                before_idx -= 1;
+                            }
+                            
+                            // Look for context after (up to MAX_CONTEXT_LINES)
+                            let mut after_idx = i + 1;
+                            while after_idx < lines.len() && context_after.len() < 5 {
+                                let after_line = lines[after_idx].trim();
+                                if !after_line.contains("-->") && !after_line.is_empty() {
+                                    // Find timing for this context line
+                                    if let Some(context_timing) = lines[0..after_idx]
+                                        .iter()
+                                        .rev()
+                                        .find(|line| line.contains("-->")) {
+                                        if let Some((ctx_start, ctx_end)) = parse_time_range(context_timing) {
+                                            context_after.push((after_line.to_string(), ctx_start, ctx_end));
                                         }
                                     }
-                                    after_idx += 1;
                                 }
-                                
-                                self.all_results.push(SearchResult {
-                                    file_path: file_path.clone(),
-                                    text: text.to_string(),
-                                    start_time,
-                                    end_time,
-                                    context_before,
-                                    context_after,
-                                });
+                                after_idx += 1;
                             }
+                            
+                            results.push(SearchResult {
+                                file_path: file_path.clone(),
+                                text: text.to_string(),
+                                start_time,
+                                end_time,
+                                context_before,
+                                context_after,
+                            });
                         }
                     }
                 }
             }
         }
         
-        self.status_message = format!("Loaded {} samples", self.all_results.len());
-        Ok(())
+        Ok(results)
     }
     
     fn filter_results(&mut self) {
@@ -356,18 +793,9 @@ impl App {
             return false;
         }
         
-        // Compare durations with tolerance - Duration doesn't have abs()
-        let start_diff = if *start1 > *start2 {
-            *start1 - *start2
-        } else {
-            *start2 - *start1
-        };
-        
-        let end_diff = if *end1 > *end2 {
-            *end1 - *end2
-        } else {
-            *end2 - *end1
-        };
+        // Compare durations with tolerance
+        let start_diff = start1.abs_diff(*start2);
+        let end_diff = end1.abs_diff(*end2);
         
         // Consider it a match if timings are within 10ms
         start_diff < Duration::from_millis(10) && 
@@ -411,6 +839,8 @@ impl App {
                                 is_match: false, // This is context, not a match
                                 original_start: *ctx_start,
                                 original_end: *ctx_end,
+                                fades: FadeOverride::default(),
+                                processing: ChainChoice::default(),
                             });
                         }
                     }
@@ -426,6 +856,8 @@ impl App {
                 is_match: true, // This is a match
                 original_start: result.start_time, // Store original values
                 original_end: result.end_time,
+                fades: FadeOverride::default(),
+                processing: ChainChoice::default(),
             });
             
             // Add context after if enabled
@@ -449,6 +881,8 @@ impl App {
                             is_match: false, // This is context, not a match
                             original_start: *ctx_start,
                             original_end: *ctx_end,
+                            fades: FadeOverride::default(),
+                            processing: ChainChoice::default(),
                         });
                     }
                 }
@@ -456,90 +890,850 @@ impl App {
         }
     }
     
-    // Extract a sample from any line in flat_results
-    fn extract_flat_line(&self, idx: usize) -> Result<String> {
+    // Extract a sample from any line in flat_results, returning the files written
+    fn extract_flat_line(&self, idx: usize) -> Result<Vec<PathBuf>> {
         if let Some(line) = self.flat_results.get(idx) {
-            // Get corresponding wav file path
-            let wav_path = line.file_path.with_extension("wav");
-            
-            if !wav_path.exists() {
-                return Err(ParasiteError::AudioProcessing(format!("WAV file not found: {:?}", wav_path)).into());
+            return self.extract_line(line, &Self::sample_name(line));
+        }
+        
+        Err(ParasiteError::AudioProcessing("No line selected".to_string()).into())
+    }
+    
+    // Move the selected line's boundaries to the detected speech onset and offset
+    fn snap_selected(&mut self) {
+        let Some(idx) = self.selected_idx.filter(|&idx| idx < self.flat_results.len()) else {
+            return;
+        };
+        
+        let line = &self.flat_results[idx];
+        let wav_path = line.file_path.with_extension("wav");
+        let snapped = snap::snap(&wav_path, line.start_time, line.end_time, &self.settings.snap)
+            .map(|(start, end)| self.clamp_range(idx, start, end));
+        let line = &mut self.flat_results[idx];
+        match snapped {
+            Ok((start, end)) if (start, end) == (line.start_time, line.end_time) => {
+                self.status_message = "Snap: no clear speech boundaries found, timing unchanged".to_string();
             }
-            
-            // Generate output filename based on selected text (first few words)
-            let text_words: Vec<_> = line.text.split_whitespace().take(3).collect();
-            let output_name = text_words.join("_").to_lowercase();
-            let output_path = PathBuf::from(format!("{}/{}.wav", self.output_dir, output_name));
-            
-            // Ensure we have a valid duration (start before end)
-            if line.end_time <= line.start_time {
-                return Err(ParasiteError::AudioProcessing("Invalid time range: end time must be after start time".to_string()).into());
+            Ok((start, end)) => {
+                let start_delta = start.as_millis() as i64 - line.start_time.as_millis() as i64;
+                let end_delta = end.as_millis() as i64 - line.end_time.as_millis() as i64;
+                line.start_time = start;
+                line.end_time = end;
+                self.status_message = format!(
+                    "Snapped to speech: start {:+}ms, end {:+}ms (Esc to reset)",
+                    start_delta, end_delta
+                );
             }
-            
-            // Use ffmpeg to extract the segment with full timestamp precision
-            let output = Command::new("ffmpeg")
-                .args([
-                    "-i", &wav_path.to_string_lossy(),
-                    "-ss", &format!("{}", line.start_time.as_secs_f64()),
-                    "-t", &format!("{}", (line.end_time - line.start_time).as_secs_f64()),
-                    "-c:a", "copy",
-                    &output_path.to_string_lossy(),
-                    "-y" // Overwrite if exists
-                ])
-                .output()?;
-        
-            if !output.status.success() {
-                let error = String::from_utf8_lossy(&output.stderr);
-                return Err(ParasiteError::AudioProcessing(format!("ffmpeg error: {}", error)).into());
+            Err(e) => self.status_message = format!("Snap error: {}", e),
+        }
+    }
+    
+    // The range actually extracted and previewed for a line, including padding
+    fn effective_range(&self, line: &DisplayLine) -> (Duration, Duration) {
+        let padding = &self.settings.padding;
+        if padding.is_zero() {
+            return (line.start_time, line.end_time);
+        }
+        padding.apply(line.start_time, line.end_time, &self.padding_bounds(line))
+    }
+    
+    // The stretch of the source shown around a line. It covers the current,
+    // original and padded ranges with a margin, so it follows boundary
+    // adjustments.
+    fn view_range(&self, line: &DisplayLine) -> (Duration, Duration) {
+        if let Some(drag) = self.drag {
+            return drag.view;
+        }
+        let ranges = [
+            (line.start_time, line.end_time),
+            (line.original_start, line.original_end),
+            self.effective_range(line),
+        ];
+        waveform::view_range(&ranges, self.file_lengths.get(&line.file_path).copied())
+    }
+    
+    // Load the audio around the selected line if the view has moved
+    fn update_waveform(&mut self) {
+        let Some(line) = self.selected_idx.and_then(|idx| self.flat_results.get(idx)).filter(|_| self.show_waveform) else {
+            self.waveform = None;
+            return;
+        };
+        let source = line.file_path.with_extension("wav");
+        let (start, end) = self.view_range(line);
+        if self.waveform.as_ref().is_some_and(|waveform| waveform.shows(&source, start, end)) {
+            return;
+        }
+        self.waveform = Waveform::load(&source, start, end).ok();
+    }
+    
+    // Analyse the selected line's view for a pane `width` cells wide if the
+    // view or size has changed. Reads the master when previews do, since
+    // transcription audio stops at 8kHz, below most of a sibilant.
+    fn update_spectrogram(&mut self, width: u16) {
+        let Some(line) = self.selected_idx.and_then(|idx| self.flat_results.get(idx)).filter(|_| self.show_spectrogram) else {
+            self.spectrogram = None;
+            return;
+        };
+        let (start, end) = self.view_range(line);
+        let (source, from, to) = match self.masters.get(&line.file_path).filter(|_| self.preview_master) {
+            Some(master) => (master.path.clone(), master.alignment.map(start), master.alignment.map(end)),
+            None => (line.file_path.with_extension("wav"), start, end),
+        };
+        // Half blocks give two frequency bands per row
+        let columns = width.saturating_sub(2) as usize;
+        let rows = SPECTROGRAM_HEIGHT.saturating_sub(2) as usize * 2;
+        if self.spectrogram.as_ref().is_some_and(|spectrogram| spectrogram.shows(&source, start, end, columns, rows)) {
+            return;
+        }
+        self.spectrogram = Spectrogram::compute(&source, (start, end), (from, to), columns, rows).ok();
+    }
+    
+    fn toggle_waveform(&mut self) {
+        self.show_waveform = !self.show_waveform;
+        self.status_message = format!("Waveform {}", if self.show_waveform { "shown" } else { "hidden" });
+    }
+    
+    fn toggle_spectrogram(&mut self) {
+        self.show_spectrogram = !self.show_spectrogram;
+        self.status_message = format!("Spectrogram {}", if self.show_spectrogram { "shown" } else { "hidden" });
+    }
+    
+    fn select_previous(&mut self) {
+        self.selected_idx = match self.selected_idx {
+            Some(i) if i > 0 => Some(i - 1),
+            Some(i) => Some(i),
+            None if !self.flat_results.is_empty() => Some(0),
+            None => None,
+        };
+    }
+    
+    fn select_next(&mut self) {
+        self.selected_idx = match self.selected_idx {
+            Some(i) if i + 1 < self.flat_results.len() => Some(i + 1),
+            Some(i) => Some(i),
+            None if !self.flat_results.is_empty() => Some(0),
+            None => None,
+        };
+    }
+    
+    // Preview the selected line (match or context)
+    fn preview_selected(&mut self) {
+        let Some(idx) = self.selected_idx else {
+            self.status_message = "No line selected".to_string();
+            return;
+        };
+        match self.preview_flat_line(idx) {
+            Ok(_) => {
+                let line = &self.flat_results[idx];
+                let duration_secs = (line.end_time - line.start_time).as_secs_f64();
+                let line_type = if line.is_match { "match" } else { "context" };
+                self.status_message = format!(
+                    "Preview playing ({}): \"{}\" ({:.2}s{})",
+                    line_type,
+                    line.text,
+                    duration_secs,
+                    if self.looping { ", looping" } else { "" }
+                );
             }
-            
-            return Ok(output_name);
+            Err(e) => self.status_message = format!("Preview error: {}", e),
+        }
+    }
+    
+    // Clicking a row selects it and clicking the selected row previews it.
+    // The wheel moves the selection. In the waveform and spectrogram, the
+    // selected line's start and end markers can be dragged; moves go through
+    // the same checks as the adjustment keys.
+    fn handle_mouse(&mut self, mouse: MouseEvent, screen: Rect) {
+        let chunks = layout(screen, self);
+        let position = Position { x: mouse.column, y: mouse.row };
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.select_previous(),
+            MouseEventKind::ScrollDown => self.select_next(),
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.pending_rename = None;
+                let results = Block::default().borders(Borders::ALL).inner(chunks[1]);
+                if results.contains(position) {
+                    // Rows start under the header and scroll to keep the selection in view
+                    let visible = results.height.saturating_sub(1) as usize;
+                    let first = self.selected_idx.map_or(0, |idx| (idx + 1).saturating_sub(visible));
+                    let Some(row) = (mouse.row - results.y).checked_sub(1) else {
+                        return;
+                    };
+                    let idx = first + row as usize;
+                    if idx >= self.flat_results.len() {
+                        return;
+                    }
+                    if self.selected_idx == Some(idx) {
+                        self.preview_selected();
+                    } else {
+                        self.selected_idx = Some(idx);
+                    }
+                    return;
+                }
+                if let Some((pane, view)) = self.boundary_pane(&chunks, position) {
+                    let Some(line) = self.selected_idx.and_then(|idx| self.flat_results.get(idx)) else {
+                        return;
+                    };
+                    // Grab whichever boundary is nearer, if it is within a cell
+                    let column = |time: Duration| pane_column(pane, view, time);
+                    let (to_start, to_end) = (column(line.start_time).abs_diff(mouse.column), column(line.end_time).abs_diff(mouse.column));
+                    if to_start.min(to_end) <= 1 {
+                        self.drag = Some(Drag { start: to_start <= to_end, view });
+                    }
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                let Some(drag) = self.drag else {
+                    return;
+                };
+                let (Some((idx, line)), Some((pane, _))) = (
+                    self.selected_idx.and_then(|idx| Some((idx, self.flat_results.get(idx)?))),
+                    self.boundary_pane(&chunks, position),
+                ) else {
+                    return;
+                };
+                // Pin at the limits the adjustment keys keep to rather than refusing the move
+                let target = pane_time(pane, drag.view, mouse.column).as_millis() as i64;
+                let (start_ms, end_ms) = (line.start_time.as_millis() as i64, line.end_time.as_millis() as i64);
+                let delta_ms = if drag.start {
+                    target.min(end_ms - MIN_LINE_MS).max(0) - start_ms
+                } else {
+                    target.min(self.max_end_ms(idx)).max(start_ms + MIN_LINE_MS) - end_ms
+                };
+                if delta_ms == 0 {
+                    return;
+                }
+                if drag.start {
+                    self.adjust_start_time(delta_ms);
+                } else {
+                    self.adjust_end_time(delta_ms);
+                }
+            }
+            MouseEventKind::Up(MouseButton::Left) => self.drag = None,
+            _ => {}
         }
+    }
+    
+    // The inside of the waveform or spectrogram pane at `position`, with the
+    // view it shows
+    fn boundary_pane(&self, chunks: &[Rect], position: Position) -> Option<(Rect, (Duration, Duration))> {
+        let view = match (self.drag, &self.waveform, &self.spectrogram) {
+            (Some(drag), _, _) => drag.view,
+            (None, Some(waveform), _) => (waveform.start, waveform.end),
+            (None, None, Some(spectrogram)) => (spectrogram.start, spectrogram.end),
+            _ => return None,
+        };
+        [(self.show_waveform, chunks[2]), (self.show_spectrogram, chunks[3])]
+            .into_iter()
+            .filter(|(shown, _)| *shown)
+            .map(|(_, area)| Block::default().borders(Borders::ALL).inner(area))
+            // Any column of the pane's rows, so a drag past either end pins the boundary there
+            .find(|pane| pane.width > 0 && (pane.y..pane.bottom()).contains(&position.y))
+            .map(|pane| (pane, view))
+    }
+    
+    // Neighbouring cues and the file length, which clamped padding stays within
+    fn padding_bounds(&self, line: &DisplayLine) -> Bounds {
+        let cues: Vec<&SearchResult> = self.all_results.iter()
+            .filter(|r| r.file_path == line.file_path)
+            .collect();
         
-        Err(ParasiteError::AudioProcessing("No line selected".to_string()).into())
+        let earliest = cues.iter()
+            .map(|r| r.end_time)
+            .filter(|&end| end <= line.original_start)
+            .max()
+            .unwrap_or(Duration::ZERO);
+        let next_start = cues.iter()
+            .map(|r| r.start_time)
+            .filter(|&start| start >= line.original_end)
+            .min();
+        let file_end = self.file_lengths.get(&line.file_path).copied();
+        
+        let latest = match (next_start, file_end) {
+            (Some(next), Some(end)) => Some(next.min(end)),
+            (next, end) => next.or(end),
+        };
+        Bounds { earliest, latest }
     }
     
+    // Sample name for a line: the first few words of its text
+    fn sample_name(line: &DisplayLine) -> String {
+        let text_words: Vec<_> = line.text.split_whitespace().take(3).collect();
+        text_words.join("_").to_lowercase()
+    }
     
-    // Preview any line (match or context) from the flat list
-    fn preview_flat_line(&self, idx: usize) -> Result<()> {
-        if let Some(line) = self.flat_results.get(idx) {
-            // Get corresponding wav file path
-            let wav_path = line.file_path.with_extension("wav");
-            
-            if !wav_path.exists() {
-                return Err(ParasiteError::AudioProcessing(format!("WAV file not found: {:?}", wav_path)).into());
+    // Extract a single line to the output directory under `output_name`,
+    // returning the file written for each output variant
+    fn extract_line(&self, line: &DisplayLine, output_name: &str) -> Result<Vec<PathBuf>> {
+        let plan = self.plan_extraction(line, true)?;
+        
+        let output_dir = std::path::Path::new(&self.output_dir);
+        let slice_path = output_dir.join(format!(".{}.slice.wav", output_name));
+        
+        // Use ffmpeg to extract the segment with full timestamp precision
+        let output = Command::new("ffmpeg")
+            .args([
+                "-i", &plan.source.to_string_lossy(),
+                "-ss", &format!("{}", plan.start.as_secs_f64()),
+                "-t", &format!("{}", (plan.end - plan.start).as_secs_f64()),
+                "-c:a", "copy",
+                &slice_path.to_string_lossy(),
+                "-y" // Overwrite if exists
+            ])
+            .output()?;
+    
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr);
+            return Err(ParasiteError::AudioProcessing(format!("ffmpeg error: {}", error)).into());
+        }
+        
+        // Embed transcript, speaker and source details so DAWs can show them
+        let meta = SampleMetadata::new(
+            &line.text,
+            &plan.source,
+            plan.start,
+            plan.end - plan.start,
+        );
+        let result = self.write_outputs(&plan, &slice_path, output_name, &meta);
+        std::fs::remove_file(&slice_path)?;
+        result
+    }
+    
+    // Write every output variant of a freshly cut slice
+    fn write_outputs(&self, plan: &Extraction, slice_path: &std::path::Path, name: &str, meta: &SampleMetadata) -> Result<Vec<PathBuf>> {
+        let variants = &self.settings.outputs;
+        let paths = output::variant_paths(std::path::Path::new(&self.output_dir), name, variants);
+        
+        // Only decode the slice if something needs to change it
+        let source_format = audio::wav_format(slice_path)?;
+        let needs_decode = plan.has_processing() || variants.iter().any(|v| !v.is_source_format());
+        let buffer = if needs_decode {
+            let mut buffer = AudioBuffer::read_wav(slice_path)?;
+            plan.process(&mut buffer);
+            Some(buffer)
+        } else {
+            None
+        };
+        
+        for (variant, path) in variants.iter().zip(&paths) {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
             }
-            
-            // Ensure we have a valid duration (start before end)
-            if line.end_time <= line.start_time {
-                return Err(ParasiteError::AudioProcessing("Invalid time range: end time must be after start time".to_string()).into());
+            match &buffer {
+                Some(buffer) => output::write_variant(buffer, source_format, plan.has_processing(), variant, path, meta)?,
+                None => {
+                    std::fs::copy(slice_path, path)?;
+                    metadata::write_wav_metadata(path, meta)?;
+                }
             }
+        }
+        Ok(paths)
+    }
+    
+    // Path of an extracted sample's WAV, for exports that read it back
+    fn sample_wav_path(&self, name: &str) -> Result<PathBuf> {
+        let paths = output::variant_paths(std::path::Path::new(&self.output_dir), name, &self.settings.outputs);
+        self.settings.outputs.iter()
+            .zip(paths)
+            .find(|(variant, _)| variant.format == OutputFormat::Wav)
+            .map(|(_, path)| path)
+            .ok_or_else(|| ParasiteError::AudioProcessing("Exporting needs a WAV output variant".to_string()).into())
+    }
+    
+    // Work out what extracting `line` will write: the source range after
+    // snapping, padding and zero-crossing moves, and the processing applied to
+    // it. Preview plays the same plan so what we hear is what gets written.
+    //
+    // Timing is worked out against the transcription audio, then mapped onto
+    // the master when there is one and `use_master` is set.
+    fn plan_extraction(&self, line: &DisplayLine, use_master: bool) -> Result<Extraction> {
+        // Get corresponding wav file path
+        let wav_path = line.file_path.with_extension("wav");
+        
+        if !wav_path.exists() {
+            return Err(ParasiteError::AudioProcessing(format!("WAV file not found: {:?}", wav_path)).into());
+        }
+        
+        // Ensure we have a valid duration (start before end)
+        if line.end_time <= line.start_time {
+            return Err(ParasiteError::AudioProcessing("Invalid time range: end time must be after start time".to_string()).into());
+        }
+        
+        // Batch snapping leaves lines adjusted by hand alone
+        let mut line = line.clone();
+        if self.settings.snap_on_extract && !line.is_adjusted() {
+            (line.start_time, line.end_time) = snap::snap(&wav_path, line.start_time, line.end_time, &self.settings.snap)?;
+        }
+        let (mut start, mut end) = self.effective_range(&line);
+        let mut source = wav_path;
+        if let Some(master) = self.masters.get(&line.file_path).filter(|_| use_master) {
+            source = master.path.clone();
+            start = master.alignment.map(start);
+            end = master.alignment.map(end);
+        }
+        let fades = line.fades.apply(&self.settings.fades);
+        if fades.zero_crossing {
+            (start, end) = fade::snap_to_zero_crossings(&source, start, end)?;
+        }
+        
+        Ok(Extraction {
+            source,
+            start,
+            end,
+            chain: line.processing.resolve(self.settings.process.as_ref(), &self.settings.presets),
+            fades,
+            normalize: self.settings.normalize,
+        })
+    }
+    
+    // Render every current match into a single montage with fresh subtitles
+    fn render_supercut(&self, options: &SupercutOptions) -> Result<PathBuf> {
+        let segments: Vec<Segment> = self.filtered_results
+            .iter()
+            .map(|result| {
+                // Honour any timing adjustments made to the match in the
+                // results list, and the configured padding as extraction does
+                let adjusted = self.flat_results.iter().find(|line| {
+                    line.is_match &&
+                    line.file_path == result.file_path &&
+                    line.original_start == result.start_time &&
+                    line.original_end == result.end_time
+                });
+                let (start, end) = adjusted
+                    .map(|line| self.effective_range(line))
+                    .unwrap_or((result.start_time, result.end_time));
+                
+                match self.masters.get(&result.file_path) {
+                    Some(master) => Segment {
+                        source: master.path.clone(),
+                        text: result.text.clone(),
+                        start: master.alignment.map(start),
+                        end: master.alignment.map(end),
+                    },
+                    None => Segment {
+                        source: result.file_path.with_extension("wav"),
+                        text: result.text.clone(),
+                        start,
+                        end,
+                    },
+                }
+            })
+            .collect();
+        
+        let query_words: Vec<_> = self.search_query.split_whitespace().collect();
+        let name = if query_words.is_empty() {
+            "supercut_all".to_string()
+        } else {
+            // Query words can hold path characters, so keep to what ingest allows
+            ingest::sanitize(&format!("supercut_{}", query_words.join("_").to_lowercase()))
+        };
+        
+        // Kept out of the output directory's top level so exports don't treat it as a sample
+        let dir = PathBuf::from(&self.output_dir).join("supercuts");
+        supercut::render(&segments, &dir, &name, options)
+    }
+    
+    // Step the selected line's fade length override (default, off, presets)
+    fn cycle_fade_length(&mut self) {
+        if let Some(line) = self.selected_idx.and_then(|idx| self.flat_results.get_mut(idx)) {
+            line.fades.cycle_length();
+            self.status_message = format!("\"{}\": {}", line.text, line.fades.describe());
+        }
+    }
+    
+    // Step the selected line's fade curve override
+    fn cycle_fade_curve(&mut self) {
+        if let Some(line) = self.selected_idx.and_then(|idx| self.flat_results.get_mut(idx)) {
+            line.fades.cycle_curve();
+            self.status_message = format!("\"{}\": {}", line.text, line.fades.describe());
+        }
+    }
+    
+    // Switch preview between the master and the transcription audio
+    fn toggle_preview_source(&mut self) {
+        if self.masters.is_empty() {
+            self.status_message = "No master audio found (expected <name>.master.wav next to each recording)".to_string();
+            return;
+        }
+        self.preview_master = !self.preview_master;
+        self.status_message = format!("Previewing {} audio", if self.preview_master { "master" } else { "transcription" });
+    }
+    
+    // Look up a recording's license, length and master, aligning a new pair.
+    // Returns why its master can't be used, if it can't.
+    fn load_source(&mut self, vtt: &PathBuf) -> Option<String> {
+        let wav = vtt.with_extension("wav");
+        match SourceLicense::for_source(&wav) {
+            Some(license) => self.licenses.insert(vtt.clone(), license),
+            None => self.licenses.remove(vtt),
+        };
+        match hound::WavReader::open(&wav) {
+            Ok(reader) => {
+                let length = Duration::from_secs_f64(reader.duration() as f64 / reader.spec().sample_rate as f64);
+                self.file_lengths.insert(vtt.clone(), length);
+            }
+            Err(_) => {
+                self.file_lengths.remove(vtt);
+            }
+        }
+        
+        self.masters.remove(vtt);
+        match MasterAudio::for_source(&wav) {
+            Ok(Some(master)) => {
+                self.masters.insert(vtt.clone(), master);
+                None
+            }
+            Ok(None) => None,
+            Err(e) => Some(e.to_string()),
+        }
+    }
+    
+    // Bring recordings the watcher saw change up to date, keeping the query,
+    // the selection and any timing, fade or processing edits to lines that
+    // are still there
+    fn reload_sources(&mut self, changed: &[PathBuf]) {
+        let mut added = Vec::new();
+        let mut updated = Vec::new();
+        let mut removed = Vec::new();
+        let mut errors = Vec::new();
+        
+        for vtt in changed {
+            let name = vtt.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            let known = self.vtt_files.contains(vtt);
+            self.all_results.retain(|result| &result.file_path != vtt);
             
-            // Use ffplay to play the segment with full timestamp precision
-            
-            // Use ffplay with -nodisp to not show video window, and -autoexit to exit after playback
-            // Redirect stdout and stderr to /dev/null to prevent TUI disruption
-            let _child = Command::new("ffplay")
-                .args([
-                    "-nodisp",
-                    "-autoexit",
-                    "-loglevel", "quiet",  // Suppress all output
-                    "-ss", &format!("{}", line.start_time.as_secs_f64()),
-                    "-t", &format!("{}", (line.end_time - line.start_time).as_secs_f64()),
-                    &wav_path.to_string_lossy(),
-                ])
-                .stdout(std::process::Stdio::null())
-                .stderr(std::process::Stdio::null())
-                .spawn()?;
-            
-            // Note: We don't wait for the child process to finish to keep the UI responsive
+            if !vtt.exists() {
+                if known {
+                    self.vtt_files.retain(|file| file != vtt);
+                    self.licenses.remove(vtt);
+                    self.file_lengths.remove(vtt);
+                    self.masters.remove(vtt);
+                    self.marked.retain(|cue| &cue.file_path != vtt);
+                    removed.push(name);
+                }
+                continue;
+            }
             
-            return Ok(());
+            if !known {
+                self.vtt_files.push(vtt.clone());
+            }
+            if let Some(error) = self.load_source(vtt) {
+                errors.push(error);
+            }
+            match Self::parse_vtt(vtt) {
+                Ok(results) => {
+                    self.all_results.extend(results);
+                    if known { updated.push(name) } else { added.push(name) }
+                }
+                Err(e) => errors.push(format!("{}: {}", name, e)),
+            }
+        }
+        // Sidecars changing for a recording with no transcript touch nothing shown
+        if added.is_empty() && updated.is_empty() && removed.is_empty() && errors.is_empty() {
+            return;
         }
         
-        Err(ParasiteError::AudioProcessing("No line selected".to_string()).into())
+        // Results stay in file order, as when first loaded
+        self.all_results.sort_by_key(|result| self.vtt_files.iter().position(|file| file == &result.file_path));
+        
+        let previous = std::mem::take(&mut self.flat_results);
+        let selected = self.selected_idx.and_then(|idx| previous.get(idx)).cloned();
+        self.filter_results();
+        for line in &mut self.flat_results {
+            if let Some(old) = previous.iter().find(|old| old.same_cue(line)) {
+                line.start_time = old.start_time;
+                line.end_time = old.end_time;
+                line.fades = old.fades;
+                line.processing = old.processing.clone();
+            }
+        }
+        let kept = selected.and_then(|selected| self.flat_results.iter().position(|line| line.same_cue(&selected)));
+        self.selected_idx = kept.or_else(|| {
+            let last = self.flat_results.len().checked_sub(1)?;
+            self.selected_idx.map(|idx| idx.min(last))
+        });
+        
+        let changes: Vec<String> = [("added", &added), ("updated", &updated), ("removed", &removed)]
+            .iter()
+            .filter(|(_, names)| !names.is_empty())
+            .map(|(action, names)| format!("{} {}", action, names.join(", ")))
+            .collect();
+        self.status_message = format!("Sources changed: {} ({} samples)", changes.join("; "), self.all_results.len());
+        if let Some(error) = errors.first() {
+            self.status_message.push_str(&format!(". {} problem(s): {}", errors.len(), error));
+        }
+    }
+    
+    // Suggest a name for the selected line's recording from its keywords; a
+    // second press renames it and everything sharing its name
+    fn rename_source(&mut self) {
+        let Some(vtt) = self.selected_idx.and_then(|idx| self.flat_results.get(idx)).map(|line| line.file_path.clone()) else {
+            self.status_message = "No line selected".to_string();
+            return;
+        };
+        let old = vtt.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        
+        match self.pending_rename.take() {
+            Some((pending, name)) if pending == vtt => match self.apply_rename(&vtt, &name) {
+                Ok(()) => self.status_message = format!("Renamed {} to {}", old, name),
+                Err(e) => self.status_message = format!("Rename error: {}", e),
+            },
+            _ => {
+                let text = std::fs::read_to_string(&vtt).map(|content| namer::transcript_text(&content)).unwrap_or_default();
+                let dir = vtt.parent().unwrap_or(std::path::Path::new("."));
+                let corpus = namer::corpus_in_dir(std::path::Path::new(&self.input_dir), Some(&vtt));
+                match namer::suggest_name(&text, &corpus, &self.settings.namer) {
+                    Some(name) if name == old => self.status_message = format!("{} already has its suggested name", old),
+                    Some(name) => {
+                        let name = ingest::unique_name(dir, &name, Some(&old));
+                        self.status_message = format!("Rename {} to {}? Press ^R again to confirm", old, name);
+                        self.pending_rename = Some((vtt, name));
+                    }
+                    None => self.status_message = format!("No keywords found in {}", old),
+                }
+            }
+        }
     }
     
+    // Rename a recording's files and follow it everywhere it is keyed by path
+    fn apply_rename(&mut self, vtt: &PathBuf, name: &str) -> Result<()> {
+        let old = vtt.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let renamed = namer::rename_recording(vtt, name)?;
+        if let Some(dir) = vtt.parent() {
+            ingest::record_rename(dir, &old, name)?;
+        }
+        
+        for file in self.vtt_files.iter_mut().filter(|f| *f == vtt) {
+            *file = renamed.clone();
+        }
+        if let Some(license) = self.licenses.remove(vtt) {
+            self.licenses.insert(renamed.clone(), license);
+        }
+        if let Some(length) = self.file_lengths.remove(vtt) {
+            self.file_lengths.insert(renamed.clone(), length);
+        }
+        if let Some(mut master) = self.masters.remove(vtt) {
+            master.path = master::master_path(&renamed.with_extension("wav"));
+            self.masters.insert(renamed.clone(), master);
+        }
+        for cue in self.marked.iter_mut().filter(|cue| &cue.file_path == vtt) {
+            cue.file_path = renamed.clone();
+        }
+        
+        // Only the path changes, so lines keep their edits and the selection
+        for result in self.all_results.iter_mut().chain(self.filtered_results.iter_mut()).filter(|r| &r.file_path == vtt) {
+            result.file_path = renamed.clone();
+        }
+        for line in self.flat_results.iter_mut().chain(self.preview.as_mut().map(|p| &mut p.line)).filter(|l| &l.file_path == vtt) {
+            line.file_path = renamed.clone();
+        }
+        Ok(())
+    }
+    
+    // Step the selected line through default, no processing and each preset
+    fn cycle_processing(&mut self) {
+        if let Some(line) = self.selected_idx.and_then(|idx| self.flat_results.get_mut(idx)) {
+            line.processing.cycle(&self.settings.presets);
+            self.status_message = format!("\"{}\": {}", line.text, line.processing.describe());
+        }
+    }
+    
+    // Mark or unmark the selected line for instrument export
+    fn toggle_mark(&mut self) {
+        let Some(line) = self.selected_idx.and_then(|idx| self.flat_results.get(idx)) else {
+            self.status_message = "No line selected".to_string();
+            return;
+        };
+        
+        let key = line.cue_key();
+        if let Some(pos) = self.marked.iter().position(|m| *m == key) {
+            self.marked.remove(pos);
+            self.status_message = format!("Unmarked \"{}\" ({} marked)", line.text, self.marked.len());
+        } else {
+            self.marked.push(key);
+            self.status_message = format!("Marked \"{}\" ({} marked)", line.text, self.marked.len());
+        }
+    }
+    
+    fn is_marked(&self, line: &DisplayLine) -> bool {
+        self.marked.contains(&line.cue_key())
+    }
+    
+    // A marked cue as it stands now: the line in view, with its edits, or
+    // else the cue as transcribed (edits don't outlive a new search)
+    fn resolve_cue(&self, cue: &CueKey) -> Option<DisplayLine> {
+        if let Some(line) = self.flat_results.iter().find(|line| line.cue_key() == *cue) {
+            return Some(line.clone());
+        }
+        let result = self.all_results.iter()
+            .find(|r| r.file_path == cue.file_path && r.start_time == cue.start && r.end_time == cue.end)?;
+        Some(DisplayLine {
+            text: result.text.clone(),
+            file_path: cue.file_path.clone(),
+            start_time: cue.start,
+            end_time: cue.end,
+            is_match: false,
+            original_start: cue.start,
+            original_end: cue.end,
+            fades: FadeOverride::default(),
+            processing: ChainChoice::default(),
+        })
+    }
+    
+    // Extract the marked lines and build an instrument from them in the current format
+    fn export_marked(&self) -> Result<String> {
+        if self.marked.is_empty() {
+            return Err(ParasiteError::AudioProcessing("No lines marked for export".to_string()).into());
+        }
+        
+        let lines = self.marked.iter()
+            .map(|cue| self.resolve_cue(cue).ok_or_else(|| ParasiteError::AudioProcessing(format!(
+                "Marked line at {:.3}s in {} is no longer in its transcript",
+                cue.start.as_secs_f64(),
+                cue.file_path.display()
+            ))))
+            .collect::<Result<Vec<DisplayLine>, ParasiteError>>()?;
+        
+        // Refuse before extracting anything if the marked sources can't be combined
+        let sources: Vec<(String, Option<SourceLicense>)> = lines.iter()
+            .map(|line| {
+                let name = line.file_path.with_extension("wav").file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                (name, self.licenses.get(&line.file_path).cloned())
+            })
+            .collect();
+        let report = license::check(&sources, LicenseUse::Export);
+        if !report.is_ok() {
+            return Err(ParasiteError::License(report.conflicts.join("; ")).into());
+        }
+        
+        let mut samples = Vec::new();
+        let mut names: Vec<String> = Vec::new();
+        for line in &lines {
+            // Lines starting with the same words each get their own file
+            let base = Self::sample_name(line);
+            let name = (1..).map(|n| if n == 1 { base.clone() } else { format!("{}_{}", base, n) })
+                .find(|name| !names.contains(name))
+                .unwrap_or(base);
+            self.extract_line(line, &name)?;
+            samples.push(ExportSample::load(&self.sample_wav_path(&name)?)?);
+            names.push(name);
+        }
+        
+        let keymap = KeyMap::build(samples, self.export_layout, export::DEFAULT_BASE_KEY);
+        let chain = ChainOptions { gap: Duration::ZERO, normalize: false };
+        let dir = std::path::Path::new(&self.output_dir);
+        let path = export::write_export(&keymap, self.export_format, dir, "marked", &chain)?;
+        std::fs::write(dir.join("marked_ATTRIBUTION.txt"), license::attribution_list(&export::sample_sources(&keymap.samples)))?;
+        
+        let mut result = path.to_string_lossy().to_string();
+        if let Some(warning) = keymap.unplaced_warning().or_else(|| report.warnings.first().cloned()) {
+            result.push_str(&format!(" (warning: {})", warning));
+        }
+        Ok(result)
+    }
+    
+    
+    // Preview any line (match or context) from the flat list, replacing
+    // whatever was playing. The clip is cut and processed in memory exactly
+    // as it would be extracted.
+    fn preview_flat_line(&mut self, idx: usize) -> Result<()> {
+        let Some(line) = self.flat_results.get(idx).cloned() else {
+            return Err(ParasiteError::AudioProcessing("No line selected".to_string()).into());
+        };
+        let Some(player) = &self.player else {
+            return Err(ParasiteError::AudioProcessing("No audio output (see preview.backend)".to_string()).into());
+        };
+        
+        let plan = self.plan_extraction(&line, self.preview_master)?;
+        let mut buffer = AudioBuffer::read_wav_range(&plan.source, plan.start, plan.end)?;
+        if plan.has_processing() {
+            plan.process(&mut buffer);
+        }
+        player.play(buffer, self.looping);
+        
+        let start = match self.masters.get(&line.file_path).filter(|_| self.preview_master) {
+            Some(master) => master.alignment.unmap(plan.start),
+            None => plan.start,
+        };
+        self.preview = Some(Preview { line, start });
+        Ok(())
+    }
+    
+    fn stop_preview(&mut self) {
+        if let Some(player) = &self.player {
+            player.stop();
+        }
+        self.status_message = "Preview stopped".to_string();
+    }
+    
+    // Move the playhead and play on from there. Works after the preview has
+    // ended or been stopped too, so the edges of a clip can be replayed bit
+    // by bit.
+    fn scrub(&mut self, delta_ms: i64) {
+        let Some(progress) = self.player.as_ref().and_then(Player::progress) else {
+            self.status_message = "Nothing to scrub (Tab to preview)".to_string();
+            return;
+        };
+        let delta = Duration::from_millis(delta_ms.unsigned_abs());
+        let position = if delta_ms < 0 {
+            progress.position.saturating_sub(delta)
+        } else {
+            (progress.position + delta).min(progress.length)
+        };
+        if let Some(player) = &self.player {
+            player.seek(position);
+        }
+        if let Some(preview) = &self.preview {
+            self.status_message = format!("Playhead at {:.3}s ({})", (preview.start + position).as_secs_f64(), describe_playhead(preview, position));
+        }
+    }
+    
+    fn toggle_loop(&mut self) {
+        self.looping = !self.looping;
+        if let Some(player) = &self.player {
+            player.set_looping(self.looping);
+        }
+        self.status_message = format!("Loop {}", if self.looping { "on" } else { "off" });
+    }
+    
+    // Restart a looping preview whose line has been edited since it started,
+    // so the loop always plays the current boundaries, fades and processing
+    fn restart_loop(&mut self) {
+        let (Some(player), Some(Preview { line: playing, .. })) = (&self.player, &self.preview) else {
+            return;
+        };
+        if !self.looping || !player.is_playing() {
+            return;
+        }
+        let Some(idx) = self.flat_results.iter().position(|line| line.same_cue(playing)) else {
+            return;
+        };
+        let line = &self.flat_results[idx];
+        let edited = line.start_time != playing.start_time
+            || line.end_time != playing.end_time
+            || line.fades != playing.fades
+            || line.processing != playing.processing;
+        if edited {
+            if let Err(e) = self.preview_flat_line(idx) {
+                self.status_message = format!("Preview error: {}", e);
+            }
+        }
+    }
+    
+}
+
+// Where a playhead falls relative to the cue it was cut for
+fn describe_playhead(preview: &Preview, position: Duration) -> &'static str {
+    let time = preview.start + position;
+    if time < preview.line.start_time {
+        "pre-roll"
+    } else if time > preview.line.end_time {
+        "post-roll"
+    } else {
+        "in cue"
+    }
 }
 
 fn parse_time_range(line: &str) -> Option<(Duration, Duration)> {
@@ -575,15 +1769,37 @@ fn parse_timestamp(timestamp: &str) -> Option<Duration> {
     Some(Duration::from_millis(total_millis))
 }
 
-fn ui(frame: &mut Frame, app: &App) {
-    let chunks = Layout::default()
+// The screen's panes, shared by drawing and mouse handling
+fn layout(area: Rect, app: &App) -> std::rc::Rc<[Rect]> {
+    Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(5),  // Status and search bar (increased height)
             Constraint::Min(0),     // Main content
+            Constraint::Length(if app.show_waveform { WAVEFORM_HEIGHT } else { 0 }),
+            Constraint::Length(if app.show_spectrogram { SPECTROGRAM_HEIGHT } else { 0 }),
+            Constraint::Length(1),  // Preview transport
             Constraint::Length(1),  // Help
         ])
-        .split(frame.size());
+        .split(area)
+}
+
+// Screen column of `time` in a pane showing `view`
+fn pane_column(pane: Rect, (start, end): (Duration, Duration), time: Duration) -> u16 {
+    let span = (end - start).as_secs_f64();
+    let offset = time.saturating_sub(start).as_secs_f64();
+    let column = if span > 0.0 { (offset / span * pane.width as f64) as u16 } else { 0 };
+    pane.x + column.min(pane.width.saturating_sub(1))
+}
+
+// Time at the middle of a screen column in a pane showing `view`
+fn pane_time(pane: Rect, (start, end): (Duration, Duration), column: u16) -> Duration {
+    let column = column.clamp(pane.x, pane.right().saturating_sub(1)) - pane.x;
+    start + (end - start).mul_f64((column as f64 + 0.5) / pane.width.max(1) as f64)
+}
+
+fn ui(frame: &mut Frame, app: &App) {
+    let chunks = layout(frame.size(), app);
 
     // Status bar and search input
     let search_area = Layout::default()
@@ -659,11 +1875,23 @@ fn ui(frame: &mut Frame, app: &App) {
                 "0.00s".to_string() // Handle invalid duration case
             };
             
-            // Format text with prefix for context lines
-            let text = line.text.clone();
+            // License of the source recording, truncated to fit the column
+            let license = app.licenses.get(&line.file_path)
+                .map(|l| l.license.chars().take(14).collect::<String>())
+                .unwrap_or_else(|| "-".to_string());
+            
+            // Format text with prefix for context lines, flagging marked lines
+            let marked = app.is_marked(line);
+            let text = if marked {
+                format!("* {}", line.text)
+            } else {
+                line.text.clone()
+            };
             
             // Set style based on whether it's a match or context
-            let style = if line.is_match {
+            let style = if marked {
+                Style::default().fg(Color::Magenta)
+            } else if line.is_match {
                 Style::default().fg(Color::White)
             } else {
                 Style::default().fg(Color::DarkGray)
@@ -674,6 +1902,7 @@ fn ui(frame: &mut Frame, app: &App) {
                 Cell::from(start_time).style(style),
                 Cell::from(end_time).style(style),
                 Cell::from(duration).style(style),
+                Cell::from(license).style(style),
                 Cell::from(text).style(style),
             ])
         })
@@ -685,9 +1914,32 @@ fn ui(frame: &mut Frame, app: &App) {
         Constraint::Length(10), // Start time
         Constraint::Length(10), // End time
         Constraint::Length(8),  // Duration
+        Constraint::Length(15), // License
         Constraint::Percentage(100), // Text (remaining space)
     ];
     
+    // Show the padded range that will actually be extracted for the selection
+    let mut title = format!("Results ({} matches, {} total lines)", app.filtered_results.len(), app.flat_results.len());
+    if let Some(line) = app.selected_idx.and_then(|idx| app.flat_results.get(idx)) {
+        if !app.settings.padding.is_zero() {
+            let (start, end) = app.effective_range(line);
+            title.push_str(&format!(
+                " | Extracts {:.3}s-{:.3}s (-{}ms/+{}ms{})",
+                start.as_secs_f64(),
+                end.as_secs_f64(),
+                (line.start_time - start).as_millis(),
+                (end - line.end_time).as_millis(),
+                if app.settings.padding.clamp { ", clamped" } else { "" }
+            ));
+        }
+        if line.fades.is_set() {
+            title.push_str(&format!(" | {}", line.fades.describe()));
+        }
+        if line.processing.is_set() {
+            title.push_str(&format!(" | {}", line.processing.describe()));
+        }
+    }
+    
     // Create the table
     let table = Table::new(rows, widths)
         .header(Row::new(vec![
@@ -695,12 +1947,12 @@ fn ui(frame: &mut Frame, app: &App) {
             Cell::from("Start").style(Style::default().add_modifier(Modifier::BOLD)),
             Cell::from("End").style(Style::default().add_modifier(Modifier::BOLD)),
             Cell::from("Length").style(Style::default().add_modifier(Modifier::BOLD)),
+            Cell::from("License").style(Style::default().add_modifier(Modifier::BOLD)),
             Cell::from("Text").style(Style::default().add_modifier(Modifier::BOLD)),
         ]))
         .block(Block::default()
             .borders(Borders::ALL)
-            .title(format!("Results ({} matches, {} total lines)", 
-                  app.filtered_results.len(), app.flat_results.len())))
+            .title(title))
         .highlight_style(selected_style)
         .highlight_symbol("> ");
     
@@ -708,17 +1960,163 @@ fn ui(frame: &mut Frame, app: &App) {
     list_state.select(app.selected_idx);
     
     frame.render_stateful_widget(table, chunks[1], &mut list_state);
+    
+    if app.show_waveform {
+        render_waveform(frame, chunks[2], app);
+    }
+    if app.show_spectrogram {
+        render_spectrogram(frame, chunks[3], app);
+    }
+    
+    // Playhead of the current preview, with where it falls in the source
+    if let (Some(progress), Some(preview)) = (app.player.as_ref().and_then(Player::progress), &app.preview) {
+        let ratio = if progress.length.is_zero() { 0.0 } else { progress.position.as_secs_f64() / progress.length.as_secs_f64() };
+        let label = format!(
+            "{} {:.3}s / {:.3}s | {:.3}s in source, {}{}",
+            if progress.playing { "▶" } else { "■" },
+            progress.position.as_secs_f64(),
+            progress.length.as_secs_f64(),
+            (preview.start + progress.position).as_secs_f64(),
+            describe_playhead(preview, progress.position),
+            if app.looping { " | looping" } else { "" }
+        );
+        frame.render_widget(
+            LineGauge::default()
+                .ratio(ratio.clamp(0.0, 1.0))
+                .label(label)
+                .line_set(symbols::line::THICK)
+                .gauge_style(Style::default().fg(if progress.playing { Color::Green } else { Color::DarkGray })),
+            chunks[4],
+        );
+    }
 
     // Help text including context controls
     let context_help = format!("Context: {} lines", app.context_lines);
+    let preview_source = match (app.masters.is_empty(), app.preview_master) {
+        (true, _) => "",
+        (false, true) => " (master)",
+        (false, false) => " (transcription)",
+    };
     frame.render_widget(
-        Paragraph::new(format!("Type to search | +/-: context ({}) | ,/./[/]: adjust time | </>/{{/}}: fine adjust | Esc: reset time | Tab: preview | Enter: extract | q: quit", context_help))
+        Paragraph::new(format!("Type to search | +/-: context ({}) | ,/./[/]: adjust time | </>/{{/}}: fine adjust | Esc: reset time | ^B: snap | ^K/^Y: fades | ^P: processing ({}) | Tab: preview{} | ←/→: scrub | ^X: stop | ^L: loop ({}) | ^A: preview source | ^W: waveform | ^G: spectrogram | mouse: click, wheel, drag markers | ^R: rename source | Enter: extract | ^T: mark | ^E: export marked ({}, {}) | ^F: format | ^O: layout | ^S: supercut | q: quit", context_help, app.settings.process.as_ref().map_or("off", |p| p.name.as_str()), preview_source, if app.looping { "on" } else { "off" }, app.export_format.name(), app.export_layout.name()))
             .alignment(Alignment::Center),
-        chunks[2],
+        chunks[5],
     );
 }
 
-fn run_app(input_dir: String, output_dir: String) -> Result<()> {
+// Boundaries to mark over the selected line's view, in drawing order:
+// neighbouring cues, its original boundaries (once adjusted), the padded range
+// (when padding applies), its start and end, and the preview playhead
+fn boundary_markers(app: &App, line: &DisplayLine, (view_start, view_end): (Duration, Duration)) -> Vec<(Duration, Color)> {
+    let mut markers: Vec<(Duration, Color)> = app.all_results.iter()
+        .filter(|r| r.file_path == line.file_path && (r.start_time, r.end_time) != (line.original_start, line.original_end))
+        .flat_map(|r| [r.start_time, r.end_time])
+        .filter(|time| (view_start..=view_end).contains(time))
+        .map(|time| (time, Color::Blue))
+        .collect();
+    
+    let (pad_start, pad_end) = app.effective_range(line);
+    if line.is_adjusted() {
+        markers.push((line.original_start, Color::DarkGray));
+        markers.push((line.original_end, Color::DarkGray));
+    }
+    if (pad_start, pad_end) != (line.start_time, line.end_time) {
+        markers.push((pad_start, Color::Magenta));
+        markers.push((pad_end, Color::Magenta));
+    }
+    markers.push((line.start_time, Color::Green));
+    markers.push((line.end_time, Color::Red));
+    
+    match (&app.preview, app.player.as_ref().and_then(Player::progress)) {
+        (Some(preview), Some(progress)) if preview.line.same_cue(line) => markers.push((preview.start + progress.position, Color::White)),
+        _ => {}
+    }
+    markers
+}
+
+// The audio around the selected line, with its boundaries, its original
+// boundaries, the padded range and neighbouring cues marked
+fn render_waveform(frame: &mut Frame, area: Rect, app: &App) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Waveform | start green, end red, original grey, padding magenta, other cues blue");
+    let (Some(line), Some(waveform)) = (app.selected_idx.and_then(|idx| app.flat_results.get(idx)), &app.waveform) else {
+        frame.render_widget(block, area);
+        return;
+    };
+    
+    // Braille gives two dot columns per cell
+    let columns = area.width.saturating_sub(2) as usize * 2;
+    let peaks = waveform.peaks(columns);
+    let scale = waveform.peak().max(0.01) as f64;
+    let (view_start, view_end) = (waveform.start.as_secs_f64(), waveform.end.as_secs_f64());
+    let (pad_start, pad_end) = app.effective_range(line);
+    let markers = boundary_markers(app, line, (waveform.start, waveform.end));
+    
+    let canvas = Canvas::default()
+        .block(block)
+        .marker(symbols::Marker::Braille)
+        .x_bounds([view_start, view_end])
+        .y_bounds([-1.0, 1.0])
+        .paint(move |ctx| {
+            // Audio that will be extracted is drawn brighter than the rest
+            let step = (view_end - view_start) / columns.max(1) as f64;
+            for (i, &(low, high)) in peaks.iter().enumerate() {
+                let x = view_start + (i as f64 + 0.5) * step;
+                let color = if (pad_start.as_secs_f64()..=pad_end.as_secs_f64()).contains(&x) { Color::Cyan } else { Color::DarkGray };
+                ctx.draw(&CanvasLine { x1: x, y1: low as f64 / scale, x2: x, y2: high as f64 / scale, color });
+            }
+            ctx.layer();
+            
+            for &(time, color) in &markers {
+                let x = time.as_secs_f64();
+                ctx.draw(&CanvasLine { x1: x, y1: -1.0, x2: x, y2: 1.0, color });
+            }
+        });
+    frame.render_widget(canvas, area);
+}
+
+// The spectrum of the same stretch as the waveform, low frequencies at the
+// bottom, in half-block cells coloured from quiet (black) to loud (white),
+// with the waveform's markers drawn as vertical rules
+fn render_spectrogram(frame: &mut Frame, area: Rect, app: &App) {
+    let (Some(line), Some(spectrogram)) = (app.selected_idx.and_then(|idx| app.flat_results.get(idx)), &app.spectrogram) else {
+        frame.render_widget(Block::default().borders(Borders::ALL).title("Spectrogram"), area);
+        return;
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Spectrogram 0-{:.0}kHz | markers as in the waveform", spectrogram.top_frequency / 1000.0));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    
+    let columns = (inner.width as usize).min(spectrogram.columns);
+    let rows = (inner.height as usize).min(spectrogram.rows / 2);
+    let buffer = frame.buffer_mut();
+    for column in 0..columns {
+        for row in 0..rows {
+            // The upper half of each cell is the higher band
+            let band = spectrogram.rows - 1 - row * 2;
+            buffer.get_mut(inner.x + column as u16, inner.y + row as u16)
+                .set_symbol("▀")
+                .set_fg(spectrogram::heat(spectrogram.level(column, band)))
+                .set_bg(spectrogram::heat(spectrogram.level(column, band - 1)));
+        }
+    }
+    
+    let span = (spectrogram.end - spectrogram.start).as_secs_f64();
+    for (time, color) in boundary_markers(app, line, (spectrogram.start, spectrogram.end)) {
+        if time < spectrogram.start || time > spectrogram.end || span <= 0.0 {
+            continue;
+        }
+        let column = (((time - spectrogram.start).as_secs_f64() / span * columns as f64) as usize).min(columns.saturating_sub(1));
+        for row in 0..rows {
+            buffer.get_mut(inner.x + column as u16, inner.y + row as u16).set_symbol("│").set_fg(color);
+        }
+    }
+}
+
+fn run_app(input_dir: String, output_dir: String, settings: Settings) -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -729,14 +2127,95 @@ fn run_app(input_dir: String, output_dir: String) -> Result<()> {
     
     // Create app state
     let mut app = App::new(input_dir, output_dir)?;
+    match Player::new(&settings.preview) {
+        Ok(player) => app.player = Some(player),
+        Err(e) => app.status_message = format!("Preview unavailable ({} backend): {}", settings.preview.name(), e),
+    }
+    app.settings = settings;
+    
+    // Pick up recordings as they are transcribed, changed or deleted
+    let mut watcher = match SourceWatcher::new(std::path::Path::new(&app.input_dir)) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            app.status_message = format!("{}; restart to see new recordings", e);
+            None
+        }
+    };
     
     loop {
+        if let Some(changed) = watcher.as_mut().map(SourceWatcher::poll).filter(|changed| !changed.is_empty()) {
+            app.reload_sources(&changed);
+        }
+        app.restart_loop();
+        app.update_waveform();
+        app.update_spectrogram(terminal.size()?.width);
         terminal.draw(|f| ui(f, &app))?;
         
-        if event::poll(Duration::from_millis(250))? {
-            if let Event::Key(key) = event::read()? {
+        // Redraw often enough for the playhead to move smoothly
+        let playing = app.player.as_ref().is_some_and(Player::is_playing);
+        if event::poll(Duration::from_millis(if playing { 40 } else { 250 }))? {
+            let event = event::read()?;
+            if let Event::Mouse(mouse) = event {
+                app.handle_mouse(mouse, terminal.size()?);
+            }
+            if let Event::Key(key) = event {
                 if key.kind == KeyEventKind::Press {
+                    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                    // A suggested rename only stands until the next key
+                    if !(ctrl && key.code == KeyCode::Char('r')) {
+                        app.pending_rename = None;
+                    }
                     match key.code {
+                        KeyCode::Char('t') if ctrl => app.toggle_mark(),
+                        KeyCode::Char('b') if ctrl => app.snap_selected(),
+                        KeyCode::Char('k') if ctrl => app.cycle_fade_length(),
+                        KeyCode::Char('y') if ctrl => app.cycle_fade_curve(),
+                        KeyCode::Char('p') if ctrl => app.cycle_processing(),
+                        KeyCode::Char('a') if ctrl => app.toggle_preview_source(),
+                        KeyCode::Char('r') if ctrl => app.rename_source(),
+                        KeyCode::Char('x') if ctrl => app.stop_preview(),
+                        KeyCode::Char('l') if ctrl => app.toggle_loop(),
+                        KeyCode::Char('w') if ctrl => app.toggle_waveform(),
+                        KeyCode::Char('g') if ctrl => app.toggle_spectrogram(),
+                        KeyCode::Char('s') if ctrl => {
+                            let options = SupercutOptions {
+                                gap: Duration::from_millis(supercut::DEFAULT_GAP_MS),
+                                crossfade: Duration::from_millis(supercut::DEFAULT_CROSSFADE_MS),
+                            };
+                            match app.render_supercut(&options) {
+                                Ok(path) => {
+                                    app.status_message = format!(
+                                        "Supercut of {} matches saved: {}",
+                                        app.filtered_results.len(),
+                                        path.display()
+                                    );
+                                }
+                                Err(e) => app.status_message = format!("Supercut error: {}", e),
+                            }
+                        }
+                        KeyCode::Char('f') if ctrl => {
+                            app.export_format = app.export_format.next();
+                            app.status_message = format!("Export format: {}", app.export_format.name());
+                        }
+                        KeyCode::Char('o') if ctrl => {
+                            app.export_layout = app.export_layout.next();
+                            app.status_message = format!("Export layout: {}", app.export_layout.name());
+                        }
+                        KeyCode::Char('e') if ctrl => {
+                            // Extract the marked lines and write an instrument for them
+                            match app.export_marked() {
+                                Ok(path) => {
+                                    app.status_message = format!(
+                                        "Exported {} marked samples to {}",
+                                        app.marked.len(),
+                                        path
+                                    );
+                                }
+                                Err(e) => app.status_message = format!("Export error: {}", e),
+                            }
+                        }
+                        // Don't type unbound control chords into the search
+                        KeyCode::Char(_) if ctrl => {}
                         KeyCode::Char('q') => break,
                         KeyCode::Char('+') => {
                             // Increase context lines (max 5)
@@ -809,27 +2288,7 @@ fn run_app(input_dir: String, output_dir: String) -> Result<()> {
                                 app.status_message = "No line selected".to_string();
                             }
                         },
-                        KeyCode::Tab => {
-                            // Preview the selected line (match or context) (previously 'p')
-                            if let Some(idx) = app.selected_idx {
-                                match app.preview_flat_line(idx) {
-                                    Ok(_) => {
-                                        let line = &app.flat_results[idx];
-                                        let duration_secs = (line.end_time - line.start_time).as_secs_f64();
-                                        let line_type = if line.is_match { "match" } else { "context" };
-                                        app.status_message = format!(
-                                            "Preview playing ({}): \"{}\" ({:.2}s)",
-                                            line_type,
-                                            line.text,
-                                            duration_secs
-                                        );
-                                    }
-                                    Err(e) => app.status_message = format!("Preview error: {}", e),
-                                }
-                            } else {
-                                app.status_message = "No line selected".to_string();
-                            }
-                        }
+                        KeyCode::Tab => app.preview_selected(), // (previously 'p')
                         KeyCode::Char(c) => {
                             app.search_query.push(c);
                             app.filter_results();
@@ -842,14 +2301,14 @@ fn run_app(input_dir: String, output_dir: String) -> Result<()> {
                             // Extract sample on Enter from any line (match or context)
                             if let Some(idx) = app.selected_idx {
                                 match app.extract_flat_line(idx) {
-                                    Ok(sample_name) => {
+                                    Ok(paths) => {
                                         let line = &app.flat_results[idx];
                                         let duration_secs = (line.end_time - line.start_time).as_secs_f64();
                                         let line_type = if line.is_match { "match" } else { "context" };
+                                        let files: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
                                         app.status_message = format!(
-                                            "Sample saved: {}/{}.wav ({}, {:.2}s)",
-                                            app.output_dir,
-                                            sample_name,
+                                            "Sample saved: {} ({}, {:.2}s)",
+                                            files.join(", "),
                                             line_type,
                                             duration_secs
                                         );
@@ -860,22 +2319,13 @@ fn run_app(input_dir: String, output_dir: String) -> Result<()> {
                                 app.status_message = "No line selected".to_string();
                             }
                         }
-                        KeyCode::Up => {
-                            app.selected_idx = match app.selected_idx {
-                                Some(i) if i > 0 => Some(i - 1),
-                                Some(i) => Some(i),
-                                None if !app.flat_results.is_empty() => Some(0),
-                                None => None,
-                            };
-                        }
-                        KeyCode::Down => {
-                            app.selected_idx = match app.selected_idx {
-                                Some(i) if i + 1 < app.flat_results.len() => Some(i + 1),
-                                Some(i) => Some(i),
-                                None if !app.flat_results.is_empty() => Some(0),
-                                None => None,
-                            };
+                        KeyCode::Left | KeyCode::Right => {
+                            // Scrub the preview, finely with Shift
+                            let step = if key.modifiers.contains(KeyModifiers::SHIFT) { 25 } else { 100 };
+                            app.scrub(if key.code == KeyCode::Left { -step } else { step });
                         }
+                        KeyCode::Up => app.select_previous(),
+                        KeyCode::Down => app.select_next(),
                         _ => {}
                     }
                 }
@@ -895,10 +2345,168 @@ fn run_app(input_dir: String, output_dir: String) -> Result<()> {
     Ok(())
 }
 
+// Default instrument and pack names to the output directory's name
+fn default_name(output_dir: &str) -> String {
+    std::fs::canonicalize(output_dir)
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "parasite".to_string())
+}
+
+// Build an instrument from every sample in the output directory
+fn run_export(output_dir: &str, format: ExportFormat, layout: KeyLayout, base_key: u8, name: Option<String>, chain: &ChainOptions, allow_license_conflicts: bool) -> Result<()> {
+    let samples = export::samples_in_dir(output_dir)?;
+    if samples.is_empty() {
+        println!("No samples found in '{}'", output_dir);
+        return Ok(());
+    }
+    check_licenses(&samples, LicenseUse::Export, allow_license_conflicts)?;
+    
+    let name = name.unwrap_or_else(|| default_name(output_dir));
+    
+    let dir = std::path::Path::new(output_dir);
+    let keymap = KeyMap::build(samples, layout, base_key);
+    if let Some(warning) = keymap.unplaced_warning() {
+        println!("Warning: {}", warning);
+    }
+    let path = export::write_export(&keymap, format, dir, &name, chain)?;
+    std::fs::write(dir.join(format!("{}_ATTRIBUTION.txt", name)), license::attribution_list(&export::sample_sources(&keymap.samples)))?;
+    
+    println!("Wrote {} ({} samples on {} keys)", path.display(), keymap.zones.len(), keymap.group_count());
+    Ok(())
+}
+
+// Search the corpus and render every match without starting the TUI
+fn run_supercut(input_dir: String, output_dir: String, query: String, settings: Settings, options: &SupercutOptions) -> Result<()> {
+    let mut app = App::new(input_dir, output_dir)?;
+    app.settings = settings;
+    app.search_query = query;
+    app.filter_results();
+    
+    let path = app.render_supercut(options)?;
+    println!("Rendered {} matches to {} (with .vtt and .srt subtitles)", app.filtered_results.len(), path.display());
+    Ok(())
+}
+
+// Normalize the output directory's samples in place
+fn run_normalize(output_dir: &str, options: &NormalizeOptions, match_loudness: bool) -> Result<()> {
+    let paths: Vec<PathBuf> = export::samples_in_dir(output_dir)?
+        .into_iter()
+        .map(|s| s.path)
+        .collect();
+    if paths.is_empty() {
+        println!("No samples found in '{}'", output_dir);
+        return Ok(());
+    }
+    
+    let unit = options.mode.unit();
+    for report in loudness::normalize_files(&paths, options, match_loudness)? {
+        println!("{}: {:.1} -> {:.1} {}", report.path.display(), report.before, report.after, unit);
+    }
+    Ok(())
+}
+
+// Transcribe new recordings into the input directory
+fn run_ingest(input_dir: &str, files: &[PathBuf], template: Option<String>, force: bool) -> Result<()> {
+    let config = config::Config::load(input_dir)?;
+    let mut options = IngestOptions::from_config(&config)?;
+    if let Some(template) = template {
+        options.template = template;
+    }
+    options.force = force;
+    
+    let summary = ingest::ingest(files, std::path::Path::new(input_dir), &options)?;
+    println!(
+        "Ingested {} of {} file(s) into {} ({} already done, {} failed)",
+        summary.ingested.len(),
+        files.len(),
+        input_dir,
+        summary.skipped,
+        summary.failed
+    );
+    if summary.failed > 0 {
+        return Err(ParasiteError::AudioProcessing(format!("{} file(s) failed; run again to retry them", summary.failed)).into());
+    }
+    Ok(())
+}
+
+// Print license warnings, and refuse to continue on conflicts unless allowed
+fn check_licenses(samples: &[ExportSample], usage: LicenseUse, allow_conflicts: bool) -> Result<()> {
+    let report = license::check(&export::sample_sources(samples), usage);
+    for warning in &report.warnings {
+        println!("Warning: {}", warning);
+    }
+    for conflict in &report.conflicts {
+        println!("License conflict: {}", conflict);
+    }
+    
+    if report.is_ok() || allow_conflicts {
+        return Ok(());
+    }
+    Err(ParasiteError::License("sources have incompatible licenses (use --allow-license-conflicts to override)".to_string()).into())
+}
+
+// Archive the output directory's samples, one archive per requested variant
+fn run_package(output_dir: &str, name: Option<String>, variants: Vec<ExportFormat>, archive: ArchiveFormat, layout: KeyLayout, base_key: u8, allow_license_conflicts: bool) -> Result<()> {
+    let samples = export::samples_in_dir(output_dir)?;
+    if samples.is_empty() {
+        println!("No samples found in '{}'", output_dir);
+        return Ok(());
+    }
+    check_licenses(&samples, LicenseUse::Package, allow_license_conflicts)?;
+    if let Some(warning) = KeyMap::build(samples.clone(), layout, base_key).unplaced_warning().filter(|_| !variants.is_empty()) {
+        println!("Warning: {}", warning);
+    }
+    
+    let options = PackageOptions {
+        name: name.unwrap_or_else(|| default_name(output_dir)),
+        archive,
+        variants,
+        layout,
+        base_key,
+    };
+    
+    for path in package::package(&samples, output_dir, &options)? {
+        println!("Wrote {} (+ .sha256)", path.display());
+    }
+    Ok(())
+}
+
 fn main() -> Result<()> {
     // Parse command line arguments
     let args = Args::parse();
     
+    // Subcommands run without the TUI
+    if let Some(Commands::Export { format, layout, base_key, name, gap_ms, normalize, allow_license_conflicts }) = args.command {
+        let chain = ChainOptions { gap: Duration::from_millis(gap_ms), normalize };
+        return run_export(&args.output_dir, format, layout, base_key, name, &chain, allow_license_conflicts);
+    }
+    if let Some(Commands::Supercut { query, gap_ms, crossfade_ms }) = &args.command {
+        let options = SupercutOptions {
+            gap: Duration::from_millis(*gap_ms),
+            crossfade: Duration::from_millis(*crossfade_ms),
+        };
+        let settings = Settings::load(&args)?;
+        return run_supercut(args.input_dir.clone(), args.output_dir.clone(), query.join(" "), settings, &options);
+    }
+    if let Some(Commands::Normalize { mode, target, match_loudness }) = args.command {
+        // Flags override the `[normalize]` section, as for extraction
+        let config = config::Config::load(&args.input_dir)?;
+        let mut options = match (mode.or(args.normalize), NormalizeOptions::from_config(&config)?) {
+            (None, Some(options)) => options,
+            (mode, _) => NormalizeOptions::new(mode.unwrap_or(NormalizeMode::Lufs)),
+        };
+        options.target = target.or(args.normalize_target).unwrap_or(options.target);
+        options.true_peak = args.true_peak.unwrap_or(options.true_peak);
+        return run_normalize(&args.output_dir, &options, match_loudness);
+    }
+    if let Some(Commands::Ingest { files, template, force }) = args.command {
+        return run_ingest(&args.input_dir, &files, template, force);
+    }
+    if let Some(Commands::Package { name, variant, archive, layout, base_key, allow_license_conflicts }) = args.command {
+        return run_package(&args.output_dir, name, variant, archive, layout, base_key, allow_license_conflicts);
+    }
+    
     // Check if input directory exists
     if !std::path::Path::new(&args.input_dir).exists() {
         println!("Warning: Input directory '{}' does not exist. Creating it...", args.input_dir);
@@ -912,9 +2520,10 @@ fn main() -> Result<()> {
     }
     
     // Run the application
-    if let Err(err) = run_app(args.input_dir, args.output_dir) {
+    let settings = Settings::load(&args)?;
+    if let Err(err) = run_app(args.input_dir, args.output_dir, settings) {
         eprintln!("Error: {}", err);
     }
     
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,291 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::audio::AudioBuffer;
+use crate::ParasiteError;
+
+// Masters sit next to the transcription audio as `<name>.master.wav`
+pub const MASTER_SUFFIX: &str = "master";
+
+// Audio compared at each alignment anchor, and the furthest the master may
+// be shifted from the transcription audio
+const ANCHOR_WINDOW: Duration = Duration::from_secs(20);
+const MAX_OFFSET: Duration = Duration::from_secs(2);
+// Envelope resolution used for the comparison
+const ENVELOPE_HOP: Duration = Duration::from_millis(1);
+// Weakest normalised correlation accepted as a match
+const MIN_CORRELATION: f64 = 0.5;
+// Largest speed difference accepted between the two files
+const MAX_DRIFT: f64 = 0.01;
+
+// How transcript times map onto the master: master = transcript * scale + offset
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Alignment {
+    pub offset: f64, // Seconds
+    pub scale: f64,
+}
+
+impl Default for Alignment {
+    fn default() -> Alignment {
+        Alignment { offset: 0.0, scale: 1.0 }
+    }
+}
+
+impl Alignment {
+    pub fn map(&self, time: Duration) -> Duration {
+        Duration::from_secs_f64((time.as_secs_f64() * self.scale + self.offset).max(0.0))
+    }
+
+    // Transcript time for a master time
+    pub fn unmap(&self, time: Duration) -> Duration {
+        Duration::from_secs_f64(((time.as_secs_f64() - self.offset) / self.scale).max(0.0))
+    }
+
+    // Read a `.align` sidecar of `offset_ms = ` and `scale = ` lines. The
+    // file may have been edited by hand, so anything unreadable is an error
+    // naming the line rather than a reason to measure again.
+    fn read(sidecar: &Path) -> Result<Alignment> {
+        let content = std::fs::read_to_string(sidecar)?;
+        let mut alignment = Alignment::default();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || ParasiteError::Config(format!("{} line {}: invalid alignment '{}'", sidecar.display(), number + 1, line));
+            let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+            let value: f64 = value.trim().parse().map_err(|_| invalid())?;
+            match key.trim() {
+                "offset_ms" => alignment.offset = value / 1000.0,
+                "scale" if value > 0.0 => alignment.scale = value,
+                _ => return Err(invalid().into()),
+            }
+        }
+        Ok(alignment)
+    }
+
+    fn write(&self, sidecar: &Path, master: &Path) -> Result<()> {
+        let name = master.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let content = format!(
+            "# Transcript times mapped onto {}: master = transcript * scale + offset\n\
+             offset_ms = {:.3}\n\
+             scale = {:.8}\n",
+            name,
+            self.offset * 1000.0,
+            self.scale,
+        );
+        std::fs::write(sidecar, content)?;
+        Ok(())
+    }
+}
+
+// High-quality audio for a recording whose transcript was made from a
+// downmixed copy
+#[derive(Clone, Debug)]
+pub struct MasterAudio {
+    pub path: PathBuf,
+    pub alignment: Alignment,
+}
+
+impl MasterAudio {
+    // The master for a transcription WAV, if there is one. The alignment is
+    // read from the `<name>.master.align` sidecar, or measured and saved
+    // there the first time (edit or delete the sidecar to correct it).
+    pub fn for_source(transcription: &Path) -> Result<Option<MasterAudio>> {
+        let path = master_path(transcription);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let sidecar = path.with_extension("align");
+        let alignment = if sidecar.exists() {
+            Alignment::read(&sidecar)?
+        } else {
+            let alignment = align(transcription, &path)?;
+            alignment.write(&sidecar, &path)?;
+            alignment
+        };
+        Ok(Some(MasterAudio { path, alignment }))
+    }
+}
+
+// `<name>.master.wav` for `<name>.wav`
+pub fn master_path(transcription: &Path) -> PathBuf {
+    let stem = transcription.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    transcription.with_file_name(format!("{}.{}.wav", stem, MASTER_SUFFIX))
+}
+
+// `<name>.wav` for a master's `<name>.master.wav`, so the master can share
+// the recording's name and sidecars
+pub fn transcription_path(master: &Path) -> Option<PathBuf> {
+    let stem = master.file_stem()?.to_string_lossy();
+    let name = stem.strip_suffix(&format!(".{}", MASTER_SUFFIX))?;
+    Some(master.with_file_name(format!("{}.wav", name)))
+}
+
+// Measure how the master lines up with the transcription audio by
+// cross-correlating their envelopes near the start and near the end. Two
+// anchors give both a fixed offset (encoder or resampler delay) and any
+// drift between the files' clocks; short recordings use a single anchor.
+pub fn align(transcription: &Path, master: &Path) -> Result<Alignment> {
+    let reader = hound::WavReader::open(transcription)
+        .map_err(|e| ParasiteError::AudioProcessing(format!("Cannot read {:?}: {}", transcription, e)))?;
+    let length = Duration::from_secs_f64(reader.duration() as f64 / reader.spec().sample_rate as f64);
+
+    let first = MAX_OFFSET.min(length);
+    let start_offset = offset_at(transcription, master, first)?;
+    let last = length.saturating_sub(ANCHOR_WINDOW + MAX_OFFSET);
+    if last <= first + ANCHOR_WINDOW {
+        return Ok(Alignment { offset: start_offset, scale: 1.0 });
+    }
+
+    // Each offset is measured across a window, so it holds for the window's middle
+    let end_offset = offset_at(transcription, master, last)?;
+    let span = (last - first).as_secs_f64();
+    let scale = 1.0 + (end_offset - start_offset) / span;
+    if (scale - 1.0).abs() > MAX_DRIFT {
+        return Err(ParasiteError::AudioProcessing(format!(
+            "{:?} drifts {:.2}% from its transcription audio; check it's the same recording",
+            master,
+            (scale - 1.0) * 100.0
+        )).into());
+    }
+    let first_centre = (first + ANCHOR_WINDOW / 2).as_secs_f64();
+    Ok(Alignment { offset: start_offset - (scale - 1.0) * first_centre, scale })
+}
+
+// Offset (seconds) of the master relative to the transcription audio around
+// transcript time `anchor`
+fn offset_at(transcription: &Path, master: &Path, anchor: Duration) -> Result<f64> {
+    let reference = envelope(&AudioBuffer::read_wav_range(transcription, anchor, anchor + ANCHOR_WINDOW)?);
+    let search_start = anchor.saturating_sub(MAX_OFFSET);
+    let search = envelope(&AudioBuffer::read_wav_range(master, search_start, anchor + ANCHOR_WINDOW + MAX_OFFSET)?);
+    if reference.is_empty() || search.len() < reference.len() {
+        return Err(ParasiteError::AudioProcessing(format!("{:?} is too short to align", master)).into());
+    }
+
+    // Normalised correlation at each lag, from the start of the search window
+    let correlation: Vec<f64> = (0..=search.len() - reference.len())
+        .map(|lag| {
+            let window = &search[lag..lag + reference.len()];
+            let dot: f64 = reference.iter().zip(window).map(|(a, b)| a * b).sum();
+            let energy: f64 = window.iter().map(|b| b * b).sum();
+            if energy > 0.0 { dot / energy.sqrt() } else { 0.0 }
+        })
+        .collect();
+    let reference_norm = reference.iter().map(|a| a * a).sum::<f64>().sqrt();
+
+    let (best, &peak) = correlation.iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .unwrap_or((0, &0.0));
+    if reference_norm == 0.0 || peak / reference_norm < MIN_CORRELATION {
+        return Err(ParasiteError::AudioProcessing(format!("Cannot align {:?} with its transcription audio", master)).into());
+    }
+
+    // Parabolic interpolation between neighbouring lags for sub-hop precision
+    let refined = match (best.checked_sub(1).map(|i| correlation[i]), correlation.get(best + 1)) {
+        (Some(before), Some(&after)) => {
+            let curvature = before - 2.0 * peak + after;
+            if curvature < 0.0 { best as f64 + 0.5 * (before - after) / curvature } else { best as f64 }
+        }
+        _ => best as f64,
+    };
+
+    let hop = ENVELOPE_HOP.as_secs_f64();
+    Ok(search_start.as_secs_f64() + refined * hop - anchor.as_secs_f64())
+}
+
+// Mean absolute level per hop with the average removed, so files at
+// different rates, channel counts and gains can be compared
+fn envelope(buffer: &AudioBuffer) -> Vec<f64> {
+    let mono = buffer.to_mono();
+    let hop = ((ENVELOPE_HOP.as_secs_f64() * mono.sample_rate as f64).round() as usize).max(1);
+    let levels: Vec<f64> = mono.samples
+        .chunks_exact(hop)
+        .map(|chunk| chunk.iter().map(|s| s.abs() as f64).sum::<f64>() / hop as f64)
+        .collect();
+
+    let mean = levels.iter().sum::<f64>() / levels.len().max(1) as f64;
+    levels.into_iter().map(|level| level - mean).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 8000;
+
+    // Noise shaped by a syllable-like envelope that changes every 60ms, the
+    // same for every `seed`
+    fn speech(seed: u32, seconds: f64) -> Vec<f32> {
+        let mut state = seed;
+        let mut random = move || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as f32 / (1 << 24) as f32
+        };
+        let segment = (RATE as f64 * 0.06) as usize;
+        let mut samples = Vec::new();
+        while samples.len() < (seconds * RATE as f64) as usize {
+            let level = random();
+            samples.extend((0..segment).map(|_| level * (random() * 2.0 - 1.0)));
+        }
+        samples
+    }
+
+    fn write(name: &str, samples: Vec<f32>) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("parasite-align-{}-{}.wav", name, std::process::id()));
+        AudioBuffer { sample_rate: RATE, channels: 1, samples }.write_wav(&path, 16, false).unwrap();
+        path
+    }
+
+    #[test]
+    fn finds_a_delayed_master() {
+        let voice = speech(1, 10.0);
+        let transcription = write("delayed", voice.clone());
+        // A quarter second of encoder delay, at a different gain
+        let mut delayed = vec![0.0; RATE as usize / 4];
+        delayed.extend(voice.iter().map(|s| s * 0.5));
+        let master = write("delayed.master", delayed);
+
+        let alignment = align(&transcription, &master);
+        let _ = std::fs::remove_file(&transcription);
+        let _ = std::fs::remove_file(&master);
+
+        let alignment = alignment.unwrap();
+        assert!((alignment.offset - 0.25).abs() < 0.002, "{:?}", alignment);
+        assert_eq!(alignment.scale, 1.0);
+    }
+
+    #[test]
+    fn finds_a_master_that_starts_early() {
+        let voice = speech(2, 10.0);
+        let transcription = write("early", voice.clone());
+        let master = write("early.master", voice[RATE as usize / 10..].to_vec());
+
+        let alignment = align(&transcription, &master);
+        let _ = std::fs::remove_file(&transcription);
+        let _ = std::fs::remove_file(&master);
+
+        let alignment = alignment.unwrap();
+        assert!((alignment.offset + 0.1).abs() < 0.002, "{:?}", alignment);
+    }
+
+    #[test]
+    fn refuses_an_unrelated_or_silent_master() {
+        let transcription = write("unrelated", speech(3, 10.0));
+        let unrelated = write("unrelated.master", speech(4, 10.0));
+        let silent = write("silent.master", vec![0.0; RATE as usize * 10]);
+
+        let unrelated_result = align(&transcription, &unrelated);
+        let silent_result = align(&transcription, &silent);
+        for path in [&transcription, &unrelated, &silent] {
+            let _ = std::fs::remove_file(path);
+        }
+
+        assert!(unrelated_result.is_err());
+        assert!(silent_result.is_err());
+    }
+}
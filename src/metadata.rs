@@ -0,0 +1,356 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::audio::{self, AudioBuffer};
+use crate::license::SourceLicense;
+use crate::master;
+use crate::ParasiteError;
+
+// Metadata describing an extracted sample, written into the WAV as
+// BWF bext, RIFF INFO and cue/labl chunks
+pub struct SampleMetadata {
+    pub text: String,           // Transcript text of the sample
+    pub speaker: String,        // Speaker (from a VTT voice tag) or source name
+    pub source: String,         // Source WAV the sample was cut from
+    pub source_offset: Duration, // Offset of the sample within the source
+    pub duration: Duration,     // Length of the sample
+    pub license: Option<String>, // License of the source, if it has a sidecar
+}
+
+impl SampleMetadata {
+    pub fn new(text: &str, source: &Path, source_offset: Duration, duration: Duration) -> SampleMetadata {
+        let (speaker, text) = split_voice_tag(text);
+        // A master is named after its recording
+        let recording = master::transcription_path(source).unwrap_or_else(|| source.to_path_buf());
+        let speaker = speaker.unwrap_or_else(|| {
+            recording.file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unknown")
+                .to_string()
+        });
+
+        SampleMetadata {
+            text,
+            speaker,
+            source: source.to_string_lossy().to_string(),
+            source_offset,
+            duration,
+            license: SourceLicense::for_source(source).map(|l| l.license),
+        }
+    }
+
+    // Estimate word boundaries by spreading the cue duration across the words
+    // in proportion to their length (VTT cues carry no word-level timing)
+    pub fn word_markers(&self) -> Vec<(Duration, String)> {
+        let words: Vec<&str> = self.text.split_whitespace().collect();
+        let total_chars: usize = words.iter().map(|w| w.chars().count()).sum();
+        if total_chars == 0 {
+            return Vec::new();
+        }
+
+        let mut markers = Vec::new();
+        let mut chars_so_far = 0;
+        for word in words {
+            let offset = self.duration.mul_f64(chars_so_far as f64 / total_chars as f64);
+            markers.push((offset, word.to_string()));
+            chars_so_far += word.chars().count();
+        }
+        markers
+    }
+}
+
+// Strip a leading context arrow and `<v Speaker>` voice tag from cue text
+fn split_voice_tag(text: &str) -> (Option<String>, String) {
+    let text = text.trim_start_matches(['↑', '↓']).trim();
+
+    if let Some(rest) = text.strip_prefix("<v ") {
+        if let Some(end) = rest.find('>') {
+            let speaker = rest[..end].trim().to_string();
+            let remainder = rest[end + 1..].replace("</v>", "");
+            return (Some(speaker), remainder.trim().to_string());
+        }
+    }
+
+    (None, text.to_string())
+}
+
+#[derive(Clone)]
+struct Chunk {
+    id: [u8; 4],
+    data: Vec<u8>,
+}
+
+// Rewrite a WAV file with bext, INFO and cue/labl chunks describing the sample.
+// Any existing copies of those chunks (e.g. ffmpeg's INFO/ISFT) are replaced.
+pub fn write_wav_metadata(path: &Path, meta: &SampleMetadata) -> Result<()> {
+    let bytes = fs::read(path)?;
+    let chunks = read_chunks(&bytes)?;
+
+    let fmt = chunks.iter()
+        .find(|c| &c.id == b"fmt ")
+        .ok_or_else(|| ParasiteError::AudioProcessing(format!("No fmt chunk in {:?}", path)))?;
+    if fmt.data.len() < 8 {
+        return Err(ParasiteError::AudioProcessing(format!("Truncated fmt chunk in {:?}", path)).into());
+    }
+    let sample_rate = u32::from_le_bytes([fmt.data[4], fmt.data[5], fmt.data[6], fmt.data[7]]);
+
+    let mut output = Vec::new();
+    for chunk in &chunks {
+        match &chunk.id {
+            b"bext" | b"cue " => continue,
+            b"LIST" if chunk.data.starts_with(b"INFO") || chunk.data.starts_with(b"adtl") => continue,
+            b"data" => {
+                // BWF expects bext ahead of the audio data
+                output.push(Chunk { id: *b"bext", data: bext_chunk(meta, sample_rate) });
+                output.push(Chunk { id: chunk.id, data: chunk.data.clone() });
+            }
+            _ => output.push(Chunk { id: chunk.id, data: chunk.data.clone() }),
+        }
+    }
+
+    let markers = meta.word_markers();
+    output.push(Chunk { id: *b"LIST", data: info_list(meta) });
+    if !markers.is_empty() {
+        output.push(Chunk { id: *b"cue ", data: cue_chunk(&markers, sample_rate) });
+        output.push(Chunk { id: *b"LIST", data: adtl_list(&markers) });
+    }
+
+    fs::write(path, write_chunks(&output))?;
+    Ok(())
+}
+
+// Replace a WAV's audio with `buffer`, keeping its bit depth, sample format
+// and metadata chunks (the frame count should be unchanged so cues stay valid)
+pub fn replace_audio(path: &Path, buffer: &AudioBuffer) -> Result<()> {
+    let original = read_chunks(&fs::read(path)?)?;
+    let (bits_per_sample, float) = audio::wav_format(path)?;
+    buffer.write_wav(path, bits_per_sample, float)?;
+    let rewritten = read_chunks(&fs::read(path)?)?;
+
+    let output: Vec<Chunk> = original.into_iter()
+        .filter_map(|chunk| match &chunk.id {
+            b"fmt " | b"data" => rewritten.iter().find(|c| c.id == chunk.id).cloned(),
+            _ => Some(chunk),
+        })
+        .collect();
+
+    fs::write(path, write_chunks(&output))?;
+    Ok(())
+}
+
+// Read a RIFF INFO entry (e.g. INAM for the transcript text) back out of a WAV
+pub fn read_info_entry(path: &Path, id: &[u8; 4]) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    let chunks = read_chunks(&bytes).ok()?;
+
+    let list = chunks.iter()
+        .find(|c| &c.id == b"LIST" && c.data.starts_with(b"INFO"))?;
+
+    let mut pos = 4;
+    while pos + 8 <= list.data.len() {
+        let entry_id = &list.data[pos..pos + 4];
+        let size = u32::from_le_bytes([
+            list.data[pos + 4], list.data[pos + 5], list.data[pos + 6], list.data[pos + 7],
+        ]) as usize;
+        let start = pos + 8;
+        let end = std::cmp::min(start + size, list.data.len());
+        if entry_id == id {
+            let value = String::from_utf8_lossy(&list.data[start..end]);
+            return Some(value.trim_end_matches('\0').to_string());
+        }
+        pos = end + (size & 1);
+    }
+    None
+}
+
+// Source recording a sample was cut from, as recorded in its INFO comment
+pub fn read_source(path: &Path) -> Option<std::path::PathBuf> {
+    let comment = read_info_entry(path, b"ICMT")?;
+    let source = comment.strip_prefix("Source: ")?;
+    let source = source.rsplit_once(" @ ").map(|(s, _)| s).unwrap_or(source);
+    Some(std::path::PathBuf::from(source))
+}
+
+// Replace a WAV's cue markers with `markers` (offset from the file start, label)
+pub fn write_wav_cues(path: &Path, markers: &[(Duration, String)]) -> Result<()> {
+    let bytes = fs::read(path)?;
+    let chunks = read_chunks(&bytes)?;
+    let sample_rate = chunks.iter()
+        .find(|c| &c.id == b"fmt " && c.data.len() >= 8)
+        .map(|c| u32::from_le_bytes([c.data[4], c.data[5], c.data[6], c.data[7]]))
+        .ok_or_else(|| ParasiteError::AudioProcessing(format!("No fmt chunk in {:?}", path)))?;
+
+    let mut output: Vec<Chunk> = chunks
+        .into_iter()
+        .filter(|c| &c.id != b"cue " && !(&c.id == b"LIST" && c.data.starts_with(b"adtl")))
+        .collect();
+    if !markers.is_empty() {
+        output.push(Chunk { id: *b"cue ", data: cue_chunk(markers, sample_rate) });
+        output.push(Chunk { id: *b"LIST", data: adtl_list(markers) });
+    }
+
+    fs::write(path, write_chunks(&output))?;
+    Ok(())
+}
+
+fn read_chunks(bytes: &[u8]) -> Result<Vec<Chunk>> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(ParasiteError::AudioProcessing("Not a RIFF/WAVE file".to_string()).into());
+    }
+
+    let mut chunks = Vec::new();
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = [bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]];
+        let size = u32::from_le_bytes([bytes[pos + 4], bytes[pos + 5], bytes[pos + 6], bytes[pos + 7]]) as usize;
+        let start = pos + 8;
+        // Tolerate a data chunk whose size overruns the file (streamed ffmpeg output)
+        let end = std::cmp::min(start + size, bytes.len());
+        chunks.push(Chunk { id, data: bytes[start..end].to_vec() });
+        pos = end + (size & 1);
+    }
+    Ok(chunks)
+}
+
+fn write_chunks(chunks: &[Chunk]) -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(b"WAVE");
+    for chunk in chunks {
+        body.extend_from_slice(&chunk.id);
+        body.extend_from_slice(&(chunk.data.len() as u32).to_le_bytes());
+        body.extend_from_slice(&chunk.data);
+        if chunk.data.len() % 2 == 1 {
+            body.push(0);
+        }
+    }
+
+    let mut output = Vec::with_capacity(body.len() + 8);
+    output.extend_from_slice(b"RIFF");
+    output.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    output.extend_from_slice(&body);
+    output
+}
+
+// Text as printable ASCII, as bext requires; anything else becomes '?'
+fn ascii(value: &str) -> String {
+    value.chars().map(|c| if c == ' ' || c.is_ascii_graphic() { c } else { '?' }).collect()
+}
+
+// Fixed-width, NUL-padded ASCII field as used throughout bext
+fn fixed_field(value: &str, len: usize) -> Vec<u8> {
+    let mut field = ascii(value).into_bytes();
+    field.resize(len, 0);
+    field
+}
+
+// EBU Tech 3285 broadcast extension chunk (version 1)
+fn bext_chunk(meta: &SampleMetadata, sample_rate: u32) -> Vec<u8> {
+    let time_reference = (meta.source_offset.as_secs_f64() * sample_rate as f64).round() as u64;
+    // The reference only has room for the recording's name, not its path
+    let source_name = Path::new(&meta.source).file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut data = Vec::new();
+    data.extend(fixed_field(&meta.text, 256));        // Description
+    data.extend(fixed_field("parasite", 32));         // Originator
+    data.extend(fixed_field(&source_name, 32));       // OriginatorReference
+    data.extend(fixed_field("", 10));                 // OriginationDate
+    data.extend(fixed_field("", 8));                  // OriginationTime
+    data.extend_from_slice(&(time_reference as u32).to_le_bytes());         // TimeReferenceLow
+    data.extend_from_slice(&((time_reference >> 32) as u32).to_le_bytes()); // TimeReferenceHigh
+    data.extend_from_slice(&1u16.to_le_bytes());      // Version
+    data.extend(fixed_field("", 64));                 // UMID
+    data.extend(fixed_field("", 190));                // Loudness fields + reserved
+    data.extend_from_slice(ascii(&format!("A=PCM,T=parasite,S={}\r\n", meta.source)).as_bytes()); // CodingHistory
+    // Keep the chunk even-sized; some readers ignore the RIFF pad byte
+    if data.len() % 2 == 1 {
+        data.push(0);
+    }
+    data
+}
+
+// RIFF INFO sub-chunks are NUL-terminated strings
+fn info_entry(list: &mut Vec<u8>, id: &[u8; 4], value: &str) {
+    let mut text = value.as_bytes().to_vec();
+    text.push(0);
+    list.extend_from_slice(id);
+    list.extend_from_slice(&(text.len() as u32).to_le_bytes());
+    list.extend_from_slice(&text);
+    if text.len() % 2 == 1 {
+        list.push(0);
+    }
+}
+
+fn info_list(meta: &SampleMetadata) -> Vec<u8> {
+    let mut list = b"INFO".to_vec();
+    info_entry(&mut list, b"INAM", &meta.text);
+    info_entry(&mut list, b"IART", &meta.speaker);
+    info_entry(&mut list, b"ICMT", &format!(
+        "Source: {} @ {:.3}s",
+        meta.source,
+        meta.source_offset.as_secs_f64()
+    ));
+    if let Some(license) = &meta.license {
+        info_entry(&mut list, b"ICOP", license);
+    }
+    info_entry(&mut list, b"ISFT", "parasite");
+    list
+}
+
+fn cue_chunk(markers: &[(Duration, String)], sample_rate: u32) -> Vec<u8> {
+    let mut data = (markers.len() as u32).to_le_bytes().to_vec();
+    for (i, (offset, _)) in markers.iter().enumerate() {
+        let position = (offset.as_secs_f64() * sample_rate as f64).round() as u32;
+        data.extend_from_slice(&(i as u32 + 1).to_le_bytes()); // Cue point ID
+        data.extend_from_slice(&position.to_le_bytes());      // Play order position
+        data.extend_from_slice(b"data");                       // Data chunk ID
+        data.extend_from_slice(&0u32.to_le_bytes());           // Chunk start
+        data.extend_from_slice(&0u32.to_le_bytes());           // Block start
+        data.extend_from_slice(&position.to_le_bytes());      // Sample offset
+    }
+    data
+}
+
+fn adtl_list(markers: &[(Duration, String)]) -> Vec<u8> {
+    let mut list = b"adtl".to_vec();
+    for (i, (_, word)) in markers.iter().enumerate() {
+        let mut text = word.as_bytes().to_vec();
+        text.push(0);
+        let size = 4 + text.len();
+        list.extend_from_slice(b"labl");
+        list.extend_from_slice(&(size as u32).to_le_bytes());
+        list.extend_from_slice(&(i as u32 + 1).to_le_bytes());
+        list.extend_from_slice(&text);
+        if size % 2 == 1 {
+            list.push(0);
+        }
+    }
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_field_is_padded_ascii() {
+        assert_eq!(fixed_field("ab", 4), b"ab\0\0".to_vec());
+        assert_eq!(fixed_field("abcdef", 4), b"abcd".to_vec());
+        // One '?' per character, so nothing is split when cut to width
+        assert_eq!(fixed_field("aé\tb", 4), b"a??b".to_vec());
+        assert_eq!(fixed_field("日本語", 2), b"??".to_vec());
+    }
+
+    #[test]
+    fn bext_references_the_source_by_name() {
+        let meta = SampleMetadata::new("Café", Path::new("/recordings/a very long folder name/talk.wav"), Duration::ZERO, Duration::ZERO);
+        let bext = bext_chunk(&meta, 48000);
+        assert_eq!(&bext[..5], b"Caf?\0");
+        assert_eq!(&bext[288..298], b"talk.wav\0\0");
+        assert!(bext.is_ascii());
+    }
+}
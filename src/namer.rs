@@ -0,0 +1,299 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::ParasiteError;
+
+// Words in a suggested name unless configured otherwise
+pub const DEFAULT_MAX_WORDS: usize = 4;
+// Longest phrase considered as one keyword
+const MAX_PHRASE: usize = 3;
+// Phrases must recur this often to beat single words
+const MIN_PHRASE_COUNT: usize = 2;
+
+// Common English words plus the fillers and backchannels that fill speech
+// transcripts, none of which say what a recording is about
+const STOPWORDS: &[&str] = &[
+    "a", "about", "above", "actually", "after", "again", "against", "ah", "all", "almost", "also", "am", "an",
+    "and", "any", "anyway", "are", "aren't", "around", "as", "at", "back", "basically", "be", "because", "been",
+    "before", "being", "below", "between", "bit", "both", "but", "by", "can", "can't", "cannot", "could",
+    "couldn't", "did", "didn't", "do", "does", "doesn't", "doing", "don't", "down", "during", "each", "eh", "else",
+    "er", "erm", "even", "ever", "every", "few", "for", "from", "further", "get", "gets", "getting", "go", "goes",
+    "going", "gonna", "good", "got", "gotta", "had", "hadn't", "has", "hasn't", "have", "haven't", "having", "he",
+    "he'd", "he'll", "he's", "her", "here", "here's", "hers", "herself", "hey", "hi", "him", "himself", "his",
+    "hmm", "how", "how's", "i", "i'd", "i'll", "i'm", "i've", "if", "in", "into", "is", "isn't", "it", "it's",
+    "its", "itself", "just", "kind", "kinda", "know", "let's", "like", "little", "lot", "lots", "maybe", "me",
+    "mean", "might", "mm", "more", "most", "much", "must", "mustn't", "my", "myself", "need", "no", "nor", "not",
+    "now", "of", "off", "oh", "ok", "okay", "on", "once", "one", "only", "or", "other", "ought", "our", "ours",
+    "ourselves", "out", "over", "own", "pretty", "probably", "quite", "really", "right", "said", "same", "say",
+    "says", "see", "shan't", "she", "she'd", "she'll", "she's", "should", "shouldn't", "so", "some", "something",
+    "sort", "still", "stuff", "such", "sure", "take", "than", "thank", "thanks", "that", "that's", "the", "their",
+    "theirs", "them", "themselves", "then", "there", "there's", "these", "they", "they'd", "they'll", "they're",
+    "they've", "thing", "things", "think", "this", "those", "though", "through", "to", "too", "uh", "um", "under",
+    "until", "up", "us", "very", "want", "wanna", "was", "wasn't", "way", "we", "we'd", "we'll", "we're", "we've",
+    "well", "were", "weren't", "what", "what's", "when", "when's", "where", "where's", "which", "while", "who",
+    "who's", "whom", "why", "why's", "will", "with", "won't", "would", "wouldn't", "yeah", "yep", "yes", "yet",
+    "you", "you'd", "you'll", "you're", "you've", "your", "yours", "yourself", "yourselves",
+];
+
+// Files that share a recording's name, besides its VTT
+const SIDECARS: &[&str] = &["wav", "json", "license", "master.wav", "master.align"];
+
+// Settings from the `[namer]` section
+#[derive(Clone, Debug)]
+pub struct NamerOptions {
+    pub max_words: usize,          // Words in a suggested name
+    pub stopwords: HashSet<String>, // Built-in stopwords plus `stopwords`
+}
+
+impl Default for NamerOptions {
+    fn default() -> NamerOptions {
+        NamerOptions {
+            max_words: DEFAULT_MAX_WORDS,
+            stopwords: STOPWORDS.iter().map(|w| w.to_string()).collect(),
+        }
+    }
+}
+
+impl NamerOptions {
+    pub fn from_config(config: &Config) -> Result<NamerOptions> {
+        let mut options = NamerOptions::default();
+        if let Some(extra) = config.get("namer.stopwords") {
+            options.stopwords.extend(extra.split([',', ' ']).filter(|w| !w.is_empty()).map(str::to_lowercase));
+        }
+        if let Some(words) = config.get_f32("namer.max_words")? {
+            options.max_words = words.max(1.0) as usize;
+        }
+        Ok(options)
+    }
+}
+
+// The spoken text of a VTT file, without the header, cue ids, timings or
+// voice tags
+pub fn transcript_text(vtt: &str) -> String {
+    let mut lines = Vec::new();
+    for block in vtt.split("\n\n").map(str::trim) {
+        let Some(timing) = block.lines().position(|l| l.contains("-->")) else {
+            continue;
+        };
+        for line in block.lines().skip(timing + 1) {
+            lines.push(strip_tags(line.trim()));
+        }
+    }
+    lines.join("\n")
+}
+
+// Transcripts of every VTT under `dir` except `exclude`, used as the corpus
+// keywords are weighed against
+pub fn corpus_in_dir(dir: &Path, exclude: Option<&Path>) -> Vec<String> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .map(|e| e.path().to_path_buf())
+        .filter(|p: &PathBuf| p.extension().is_some_and(|ext| ext == "vtt") && Some(p.as_path()) != exclude)
+        .filter_map(|p| std::fs::read_to_string(p).ok())
+        .map(|content| transcript_text(&content))
+        .collect()
+}
+
+// Propose a short slug naming a transcript from its most distinctive words
+// and phrases. Candidates are runs of up to three words free of stopwords
+// within a sentence, scored by TF-IDF against the other transcripts in
+// `corpus`, so words every recording shares count for little. Recurring
+// phrases are preferred to their single words, and the best candidates are
+// taken until the name is `max_words` long. Returns `None` for transcripts
+// with nothing to go on.
+pub fn suggest_name(text: &str, corpus: &[String], options: &NamerOptions) -> Option<String> {
+    let counts = phrase_counts(text, &options.stopwords);
+    if counts.is_empty() {
+        return None;
+    }
+
+    // Document frequency of each candidate across the corpus (this
+    // transcript included)
+    let documents: Vec<HashMap<Vec<String>, usize>> = corpus.iter().map(|doc| phrase_counts(doc, &options.stopwords)).collect();
+    let total = documents.len() as f64 + 1.0;
+    let idf = |phrase: &Vec<String>| {
+        let df = 1.0 + documents.iter().filter(|doc| doc.contains_key(phrase)).count() as f64;
+        (total / df).ln() + 1.0
+    };
+
+    let mut scored: Vec<(f64, &Vec<String>)> = counts.iter()
+        .filter(|(phrase, &count)| phrase.len() == 1 || count >= MIN_PHRASE_COUNT)
+        .map(|(phrase, &count)| (count as f64 * idf(phrase) * phrase.len() as f64, phrase))
+        .collect();
+    // Highest score first; ties go to the longer, then alphabetically earlier phrase
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.len().cmp(&a.1.len())).then(a.1.cmp(b.1)));
+
+    let mut words: Vec<&str> = Vec::new();
+    for (_, phrase) in scored {
+        if phrase.iter().any(|w| words.contains(&w.as_str())) || words.len() + phrase.len() > options.max_words {
+            continue;
+        }
+        words.extend(phrase.iter().map(String::as_str));
+        if words.len() == options.max_words {
+            break;
+        }
+    }
+
+    let slug = words.iter().map(|w| slug_word(w)).filter(|w| !w.is_empty()).collect::<Vec<_>>().join("-");
+    (!slug.is_empty()).then_some(slug)
+}
+
+// Rename a recording's VTT and every file sharing its name (audio, JSON
+// transcript, license, master and its alignment) to `name`, returning the
+// new VTT path. Refuses to overwrite another recording's files.
+pub fn rename_recording(vtt: &Path, name: &str) -> Result<PathBuf> {
+    let old = vtt.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let renames: Vec<(PathBuf, PathBuf)> = std::iter::once("vtt")
+        .chain(SIDECARS.iter().copied())
+        .map(|extension| {
+            (vtt.with_file_name(format!("{}.{}", old, extension)), vtt.with_file_name(format!("{}.{}", name, extension)))
+        })
+        .filter(|(from, _)| from.exists())
+        .collect();
+
+    if let Some((_, taken)) = renames.iter().find(|(_, to)| to.exists()) {
+        return Err(ParasiteError::AudioProcessing(format!("{} already exists", taken.display())).into());
+    }
+    // The VTT goes last, as in ingest, so an interrupted rename leaves the
+    // recording under its old name
+    for (from, to) in renames.iter().rev() {
+        std::fs::rename(from, to)?;
+    }
+    Ok(vtt.with_file_name(format!("{}.vtt", name)))
+}
+
+// How often each candidate phrase occurs in a text
+fn phrase_counts(text: &str, stopwords: &HashSet<String>) -> HashMap<Vec<String>, usize> {
+    let mut counts = HashMap::new();
+    for sentence in text.split(['.', '!', '?', ',', ';', ':', '\n']) {
+        let words: Vec<String> = sentence
+            .split_whitespace()
+            .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'').trim_matches('\'').to_lowercase())
+            .collect();
+
+        // Runs of meaningful words, broken by stopwords, numbers and noise
+        for run in words.split(|w| w.chars().count() < 3 || stopwords.contains(w) || !w.chars().any(char::is_alphabetic)) {
+            for n in 1..=MAX_PHRASE.min(run.len()) {
+                for phrase in run.windows(n) {
+                    *counts.entry(phrase.to_vec()).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+    counts
+}
+
+// Drop `<v Speaker>`, `<i>` and similar markup from a cue line
+fn strip_tags(line: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+// ASCII letters and digits only, accents folded where simple
+fn slug_word(word: &str) -> String {
+    word.chars()
+        .filter_map(|c| match c {
+            'à'..='å' => Some('a'),
+            'ç' => Some('c'),
+            'è'..='ë' => Some('e'),
+            'ì'..='ï' => Some('i'),
+            'ñ' => Some('n'),
+            'ò'..='ö' => Some('o'),
+            'ù'..='ü' => Some('u'),
+            c if c.is_ascii_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(max_words: usize) -> NamerOptions {
+        NamerOptions { max_words, ..NamerOptions::default() }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("parasite-namer-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn prefers_recurring_phrases_over_words_every_transcript_shares() {
+        let text = "So the sourdough starter needs feeding. The sourdough starter is alive! Today we bake bread, bread.";
+        let corpus = vec!["Today we talk about bread.".to_string(), "More bread today.".to_string()];
+        // "sourdough starter" recurs and is unique to this transcript; "bread"
+        // is as frequent but every transcript has it
+        assert_eq!(suggest_name(text, &corpus, &options(3)).as_deref(), Some("sourdough-starter-alive"));
+    }
+
+    #[test]
+    fn phrases_heard_once_fall_back_to_single_words() {
+        let text = "Modular synthesis explained.";
+        assert_eq!(suggest_name(text, &[], &options(2)).as_deref(), Some("explained-modular"));
+    }
+
+    #[test]
+    fn folds_accents_into_the_slug() {
+        assert_eq!(suggest_name("Café crème", &[], &options(4)).as_deref(), Some("cafe-creme"));
+    }
+
+    #[test]
+    fn nothing_but_stopwords_has_no_name() {
+        assert_eq!(suggest_name("", &[], &options(4)), None);
+        assert_eq!(suggest_name("Um, yeah, I mean, it's like... okay.", &[], &options(4)), None);
+    }
+
+    #[test]
+    fn reads_the_spoken_text_of_a_vtt() {
+        let vtt = "WEBVTT\n\n1\n00:00:00.000 --> 00:00:01.000\n<v Ann>Hello there\n\n00:00:01.000 --> 00:00:02.000\nGeneral <i>Kenobi</i>\n";
+        assert_eq!(transcript_text(vtt), "Hello there\nGeneral Kenobi");
+    }
+
+    #[test]
+    fn renames_the_recording_with_its_sidecars() {
+        let dir = temp_dir("rename");
+        for file in ["talk.vtt", "talk.wav", "talk.master.align", "other.wav"] {
+            std::fs::write(dir.join(file), file).unwrap();
+        }
+
+        let vtt = rename_recording(&dir.join("talk.vtt"), "sourdough").unwrap();
+        assert_eq!(vtt, dir.join("sourdough.vtt"));
+        for file in ["sourdough.vtt", "sourdough.wav", "sourdough.master.align", "other.wav"] {
+            assert!(dir.join(file).exists(), "{} missing", file);
+        }
+        assert!(!dir.join("talk.vtt").exists() && !dir.join("talk.wav").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn refuses_to_overwrite_another_recording() {
+        let dir = temp_dir("taken");
+        for file in ["talk.vtt", "talk.wav", "sourdough.wav"] {
+            std::fs::write(dir.join(file), file).unwrap();
+        }
+
+        assert!(rename_recording(&dir.join("talk.vtt"), "sourdough").is_err());
+        // Nothing moved, and the other recording's audio is untouched
+        assert!(dir.join("talk.vtt").exists() && dir.join("talk.wav").exists());
+        assert_eq!(std::fs::read_to_string(dir.join("sourdough.wav")).unwrap(), "sourdough.wav");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,280 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::Result;
+
+use crate::audio::AudioBuffer;
+use crate::config::Config;
+use crate::metadata::{self, SampleMetadata};
+use crate::ParasiteError;
+
+// Highest sample rate MP3 supports
+const MP3_MAX_RATE: u32 = 48000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Wav,
+    Aiff,
+    Flac,
+    Mp3,
+    Ogg,
+}
+
+impl OutputFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Wav => "wav",
+            OutputFormat::Aiff => "aiff",
+            OutputFormat::Flac => "flac",
+            OutputFormat::Mp3 => "mp3",
+            OutputFormat::Ogg => "ogg",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Aiff => "aif",
+            _ => self.name(),
+        }
+    }
+
+    fn is_lossy(self) -> bool {
+        matches!(self, OutputFormat::Mp3 | OutputFormat::Ogg)
+    }
+
+    // ffmpeg encoder arguments for the formats written through it
+    fn encoder_args(self) -> &'static [&'static str] {
+        match self {
+            OutputFormat::Flac => &["-c:a", "flac", "-compression_level", "8"],
+            OutputFormat::Mp3 => &["-c:a", "libmp3lame", "-q:a", "2"],
+            OutputFormat::Ogg => &["-c:a", "libvorbis", "-q:a", "6"],
+            OutputFormat::Wav | OutputFormat::Aiff => &[],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitDepth {
+    Int(u16),
+    Float, // 32-bit float
+}
+
+// One file written per extraction. Anything left unset keeps the slice's own
+// rate, depth or channel count, so the default variant is a plain WAV copy.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OutputVariant {
+    pub format: OutputFormat,
+    pub sample_rate: Option<u32>,
+    pub bit_depth: Option<BitDepth>,
+    pub channels: Option<u16>, // 1 for mono, 2 for stereo
+}
+
+impl OutputVariant {
+    // Parse `format[:option...]`, where options are a sample rate (`48000`,
+    // `44.1k`), a bit depth (`16`, `24`, `32`, `32f`) or `mono`/`stereo`,
+    // in any order: e.g. `wav:48k:24:stereo` or `mp3:44.1k`
+    pub fn parse(spec: &str) -> Result<OutputVariant, String> {
+        let mut parts = spec.split(':').map(str::trim);
+        let format = match parts.next().unwrap_or_default().to_lowercase().as_str() {
+            "wav" => OutputFormat::Wav,
+            "aif" | "aiff" => OutputFormat::Aiff,
+            "flac" => OutputFormat::Flac,
+            "mp3" => OutputFormat::Mp3,
+            "ogg" => OutputFormat::Ogg,
+            other => return Err(format!("unknown output format '{}' (expected wav, aiff, flac, mp3 or ogg)", other)),
+        };
+
+        let mut variant = OutputVariant { format, ..OutputVariant::default() };
+        for part in parts {
+            match part.to_lowercase().as_str() {
+                "mono" => variant.channels = Some(1),
+                "stereo" => variant.channels = Some(2),
+                "32f" | "float" => variant.bit_depth = Some(BitDepth::Float),
+                "8" | "16" | "24" | "32" => variant.bit_depth = Some(BitDepth::Int(part.parse().unwrap_or_default())),
+                other => {
+                    let rate = match other.strip_suffix('k') {
+                        Some(khz) => khz.parse::<f64>().ok().map(|k| (k * 1000.0).round()),
+                        None => other.parse::<f64>().ok(),
+                    };
+                    match rate {
+                        Some(rate) if (1000.0..=384000.0).contains(&rate) => variant.sample_rate = Some(rate as u32),
+                        _ => return Err(format!("invalid output option '{}'", part)),
+                    }
+                }
+            }
+        }
+
+        variant.validate()?;
+        Ok(variant)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match (self.format, self.bit_depth) {
+            (format, Some(_)) if format.is_lossy() => return Err(format!("{} has no bit depth", format.name())),
+            (OutputFormat::Aiff, Some(BitDepth::Float)) => return Err("AIFF can't hold float samples".to_string()),
+            (OutputFormat::Flac, Some(BitDepth::Float | BitDepth::Int(32))) => {
+                return Err("FLAC supports up to 24 bits".to_string())
+            }
+            _ => {}
+        }
+        if self.format == OutputFormat::Mp3 && self.sample_rate.is_some_and(|rate| rate > MP3_MAX_RATE) {
+            return Err(format!("MP3 supports sample rates up to {}Hz", MP3_MAX_RATE));
+        }
+        Ok(())
+    }
+
+    // Whether this is the slice written as-is
+    pub fn is_source_format(&self) -> bool {
+        *self == OutputVariant::default()
+    }
+
+    // Short name used for the variant's folder, e.g. `mp3-44.1k-stereo`
+    pub fn label(&self) -> String {
+        let mut parts = vec![self.format.name().to_string()];
+        if let Some(rate) = self.sample_rate {
+            parts.push(format!("{}k", rate as f64 / 1000.0));
+        }
+        match self.bit_depth {
+            Some(BitDepth::Int(bits)) => parts.push(bits.to_string()),
+            Some(BitDepth::Float) => parts.push("32f".to_string()),
+            None => {}
+        }
+        match self.channels {
+            Some(1) => parts.push("mono".to_string()),
+            Some(_) => parts.push("stereo".to_string()),
+            None => {}
+        }
+        parts.join("-")
+    }
+}
+
+// Variants from `output.variants`, a comma-separated list of specs; empty when unset
+pub fn variants_from_config(config: &Config) -> Result<Vec<OutputVariant>> {
+    let Some(value) = config.get("output.variants") else {
+        return Ok(Vec::new());
+    };
+    value.split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+        .map(|spec| {
+            OutputVariant::parse(spec)
+                .map_err(|e| ParasiteError::Config(format!("output.variants: '{}': {}", spec, e)).into())
+        })
+        .collect()
+}
+
+// Where each variant of sample `name` is written: the first in `dir` itself,
+// the rest in a folder per variant, so tools reading the output directory
+// only see one copy of each sample
+pub fn variant_paths(dir: &Path, name: &str, variants: &[OutputVariant]) -> Vec<PathBuf> {
+    variants.iter()
+        .enumerate()
+        .map(|(i, variant)| {
+            let file = format!("{}.{}", name, variant.format.extension());
+            if i == 0 { dir.join(file) } else { dir.join(variant.label()).join(file) }
+        })
+        .collect()
+}
+
+// Convert a slice to `variant` and write it to `path`. `source_format` is the
+// slice's bit depth and float flag; `modified` says whether its samples have
+// changed since it was cut. Integer output is TPDF dithered whenever it
+// loses resolution: fewer bits than the source, or audio that has been
+// processed, resampled or remixed since.
+pub fn write_variant(
+    buffer: &AudioBuffer,
+    source_format: (u16, bool),
+    modified: bool,
+    variant: &OutputVariant,
+    path: &Path,
+    meta: &SampleMetadata,
+) -> Result<()> {
+    let mut converted = buffer.clone();
+    if let Some(channels) = variant.channels.filter(|&c| c != buffer.channels) {
+        converted = converted.to_mono().with_channels(channels);
+    }
+    if let Some(rate) = variant.sample_rate {
+        converted = converted.resample(rate);
+    }
+    let modified = modified || converted.channels != buffer.channels || converted.sample_rate != buffer.sample_rate;
+
+    let (source_bits, source_float) = source_format;
+    let (bits, float) = match (variant.format, variant.bit_depth) {
+        (format, _) if format.is_lossy() => (32, true),
+        (_, Some(BitDepth::Int(bits))) => (bits, false),
+        (_, Some(BitDepth::Float)) => (32, true),
+        // AIFF and FLAC have no float samples and FLAC stops at 24 bits
+        (OutputFormat::Aiff, None) if source_float => (24, false),
+        (OutputFormat::Flac, None) if source_float || source_bits > 24 => (24, false),
+        (_, None) => (source_bits, source_float),
+    };
+    if !float && bits < 32 && (modified || source_float || bits < source_bits) {
+        converted.dither(bits);
+    }
+
+    match variant.format {
+        OutputFormat::Wav => {
+            converted.write_wav(path, bits, float)?;
+            metadata::write_wav_metadata(path, meta)?;
+        }
+        OutputFormat::Aiff => converted.write_aiff(path, bits, &[])?,
+        OutputFormat::Flac | OutputFormat::Mp3 | OutputFormat::Ogg => {
+            let temp = path.with_extension("partial.wav");
+            converted.write_wav(&temp, bits, float)?;
+            let result = encode(&temp, path, variant.format, meta);
+            std::fs::remove_file(&temp)?;
+            result?;
+        }
+    }
+    Ok(())
+}
+
+// Encode a WAV with ffmpeg, tagging it with the transcript and source
+fn encode(wav: &Path, path: &Path, format: OutputFormat, meta: &SampleMetadata) -> Result<()> {
+    let output = Command::new("ffmpeg")
+        .arg("-i").arg(wav)
+        .args(format.encoder_args())
+        .args(["-metadata", &format!("title={}", meta.text)])
+        .args(["-metadata", &format!("artist={}", meta.speaker)])
+        .args(["-metadata", &format!("comment=Source: {}", meta.source)])
+        .arg(path)
+        .arg("-y")
+        .output()?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(ParasiteError::AudioProcessing(format!("ffmpeg error: {}", error)).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_variants_in_any_order() {
+        assert_eq!(
+            OutputVariant::parse("wav:48k:24:stereo"),
+            Ok(OutputVariant { format: OutputFormat::Wav, sample_rate: Some(48000), bit_depth: Some(BitDepth::Int(24)), channels: Some(2) })
+        );
+        assert_eq!(
+            OutputVariant::parse("AIFF:mono:44100"),
+            Ok(OutputVariant { format: OutputFormat::Aiff, sample_rate: Some(44100), bit_depth: None, channels: Some(1) })
+        );
+        assert_eq!(
+            OutputVariant::parse("mp3:44.1k"),
+            Ok(OutputVariant { format: OutputFormat::Mp3, sample_rate: Some(44100), ..OutputVariant::default() })
+        );
+        assert_eq!(OutputVariant::parse("wav:32f").map(|v| v.bit_depth), Ok(Some(BitDepth::Float)));
+        assert!(OutputVariant::parse("wav").unwrap().is_source_format());
+    }
+
+    #[test]
+    fn rejects_impossible_variants() {
+        for spec in ["", "opus", "wav:fast", "wav:500", "mp3:24", "ogg:16", "aiff:32f", "flac:32", "mp3:96k"] {
+            assert!(OutputVariant::parse(spec).is_err(), "{:?} should not parse", spec);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::export::chain::ChainOptions;
+use crate::export::{self, ExportFormat, ExportSample, KeyLayout, KeyMap};
+use crate::license;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ArchiveFormat {
+    /// .zip archive
+    Zip,
+    /// Gzipped tarball (.tar.gz)
+    TarGz,
+}
+
+impl ArchiveFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarGz => "tar.gz",
+        }
+    }
+}
+
+pub struct PackageOptions {
+    pub name: String,
+    pub archive: ArchiveFormat,
+    pub variants: Vec<ExportFormat>, // One archive per format; none = samples only
+    pub layout: KeyLayout,
+    pub base_key: u8,
+}
+
+// A sample as described in the pack README
+struct PackSample {
+    file_name: String,
+    text: String,
+    duration: Duration,
+}
+
+// Bundle samples into archives under `<output_dir>/packages/`, each with a
+// README, license/attribution file and SHA-256 checksums.
+// Returns the archives written.
+pub fn package(samples: &[ExportSample], output_dir: &str, options: &PackageOptions) -> Result<Vec<PathBuf>> {
+    let packages_dir = Path::new(output_dir).join("packages");
+    fs::create_dir_all(&packages_dir)?;
+
+    let variants: Vec<Option<ExportFormat>> = if options.variants.is_empty() {
+        vec![None]
+    } else {
+        options.variants.iter().copied().map(Some).collect()
+    };
+
+    let mut archives = Vec::new();
+    for variant in variants {
+        let pack_name = match variant {
+            Some(format) => format!("{}-{}", options.name, format.name()),
+            None => options.name.clone(),
+        };
+        archives.push(build_pack(samples, &packages_dir, &pack_name, variant, options)?);
+    }
+    Ok(archives)
+}
+
+fn build_pack(
+    samples: &[ExportSample],
+    packages_dir: &Path,
+    pack_name: &str,
+    variant: Option<ExportFormat>,
+    options: &PackageOptions,
+) -> Result<PathBuf> {
+    // Stage the pack in a scratch folder, then archive it
+    let staging = packages_dir.join(pack_name);
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+
+    let mut staged = Vec::new();
+    let mut pack_samples = Vec::new();
+    for sample in samples {
+        let Some(file_name) = sample.path.file_name() else { continue };
+        let dest = staging.join(file_name);
+        fs::copy(&sample.path, &dest)?;
+
+        let reader = hound::WavReader::open(&dest)?;
+        let duration = Duration::from_secs_f64(reader.duration() as f64 / reader.spec().sample_rate as f64);
+        pack_samples.push(PackSample {
+            file_name: file_name.to_string_lossy().to_string(),
+            text: sample.text.clone(),
+            duration,
+        });
+        staged.push(ExportSample { path: dest, ..sample.clone() });
+    }
+
+    if let Some(format) = variant {
+        let keymap = KeyMap::build(staged, options.layout, options.base_key);
+        let chain = ChainOptions { gap: Duration::ZERO, normalize: false };
+        export::write_export(&keymap, format, &staging, &options.name, &chain)?;
+    }
+
+    fs::write(staging.join("README.txt"), render_readme(pack_name, variant, &pack_samples))?;
+    fs::write(staging.join("LICENSE.txt"), license::attribution_list(&export::sample_sources(samples)))?;
+    fs::write(staging.join("SHA256SUMS"), checksums(&staging)?)?;
+
+    let archive = packages_dir.join(format!("{}.{}", pack_name, options.archive.extension()));
+    match options.archive {
+        ArchiveFormat::Zip => write_zip(&staging, pack_name, &archive)?,
+        ArchiveFormat::TarGz => write_tar_gz(&staging, pack_name, &archive)?,
+    }
+    fs::remove_dir_all(&staging)?;
+
+    // Checksum of the archive itself, for publishing alongside it
+    let archive_name = archive.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let digest = sha256_file(&archive)?;
+    fs::write(packages_dir.join(format!("{}.sha256", archive_name)), format!("{}  {}\n", digest, archive_name))?;
+
+    Ok(archive)
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs_f64();
+    if secs >= 60.0 {
+        format!("{}m {:.1}s", (secs / 60.0).floor(), secs % 60.0)
+    } else {
+        format!("{:.2}s", secs)
+    }
+}
+
+fn render_readme(pack_name: &str, variant: Option<ExportFormat>, samples: &[PackSample]) -> String {
+    let total: Duration = samples.iter().map(|s| s.duration).sum();
+
+    let mut readme = String::new();
+    let _ = writeln!(readme, "{}", pack_name);
+    let _ = writeln!(readme, "{}", "=".repeat(pack_name.chars().count()));
+    let _ = writeln!(readme);
+    let _ = writeln!(readme, "Vocal sample pack created with parasite.");
+    let _ = writeln!(readme);
+    let _ = writeln!(readme, "Samples: {}", samples.len());
+    let _ = writeln!(readme, "Total duration: {}", format_duration(total));
+    if let Some(format) = variant {
+        let _ = writeln!(readme, "Instrument format: {}", format.name());
+    }
+    let _ = writeln!(readme, "License details: see LICENSE.txt");
+    let _ = writeln!(readme, "Checksums: see SHA256SUMS (verify with `sha256sum -c SHA256SUMS`)");
+    let _ = writeln!(readme);
+    let _ = writeln!(readme, "Contents");
+    let _ = writeln!(readme, "--------");
+    for sample in samples {
+        let _ = writeln!(readme, "{:<40} {:>8}  {}", sample.file_name, format_duration(sample.duration), sample.text);
+    }
+    readme
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let digest = Sha256::digest(fs::read(path)?);
+    Ok(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// Files under `dir`, relative to it and sorted
+fn pack_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.path().strip_prefix(dir).ok().map(|p| p.to_path_buf()))
+        .collect();
+    files.sort();
+    files
+}
+
+// sha256sum-compatible listing of every file in the pack
+fn checksums(dir: &Path) -> Result<String> {
+    let mut sums = String::new();
+    for file in pack_files(dir) {
+        let _ = writeln!(sums, "{}  {}", sha256_file(&dir.join(&file))?, file.to_string_lossy());
+    }
+    Ok(sums)
+}
+
+fn write_zip(staging: &Path, pack_name: &str, archive: &Path) -> Result<()> {
+    let mut zip = ZipWriter::new(File::create(archive)?);
+    let options = SimpleFileOptions::default();
+
+    for file in pack_files(staging) {
+        let entry = Path::new(pack_name).join(&file);
+        zip.start_file(entry.to_string_lossy().replace('\\', "/"), options)?;
+        zip.write_all(&fs::read(staging.join(&file))?)?;
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+fn write_tar_gz(staging: &Path, pack_name: &str, archive: &Path) -> Result<()> {
+    let encoder = GzEncoder::new(File::create(archive)?, Compression::default());
+    let mut tar = tar::Builder::new(encoder);
+    tar.append_dir_all(pack_name, staging)?;
+    tar.into_inner()?.finish()?;
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::config::Config;
+
+// Extra audio kept before and after each cue, so Whisper's tight boundaries
+// don't clip leading consonants or trailing breaths
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Padding {
+    pub pre: Duration,  // Pre-roll before the cue start
+    pub post: Duration, // Post-roll after the cue end
+    pub clamp: bool,    // Keep padding within the file and clear of neighbouring cues
+}
+
+// Limits padding may not cross when clamping
+pub struct Bounds {
+    pub earliest: Duration,         // End of the previous cue (or the file start)
+    pub latest: Option<Duration>,   // Start of the next cue or the file end, if known
+}
+
+impl Padding {
+    // Defaults from the `[padding]` section: `pre_ms`, `post_ms` and `clamp`
+    pub fn from_config(config: &Config) -> Result<Padding> {
+        Ok(Padding {
+            pre: config.get_ms("padding.pre_ms")?.unwrap_or_default(),
+            post: config.get_ms("padding.post_ms")?.unwrap_or_default(),
+            clamp: config.get_bool("padding.clamp")?.unwrap_or(false),
+        })
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.pre.is_zero() && self.post.is_zero()
+    }
+
+    // The padded range for a cue running from `start` to `end`. Clamping only
+    // ever trims the padding, never a range the user adjusted by hand.
+    pub fn apply(&self, start: Duration, end: Duration, bounds: &Bounds) -> (Duration, Duration) {
+        let mut padded_start = start.saturating_sub(self.pre);
+        let mut padded_end = end + self.post;
+
+        if self.clamp {
+            padded_start = padded_start.max(bounds.earliest.min(start));
+            if let Some(latest) = bounds.latest {
+                padded_end = padded_end.min(latest.max(end));
+            }
+        }
+
+        (padded_start, padded_end)
+    }
+}
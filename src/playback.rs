@@ -0,0 +1,681 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::audio::AudioBuffer;
+use crate::config::Config;
+use crate::ParasiteError;
+
+// Player command the pipe backend streams a WAV into by default
+const DEFAULT_PIPE_COMMAND: &str = "ffplay -nodisp -autoexit -loglevel quiet -fflags nobuffer -i -";
+// How far ahead of real time the paced backends write
+const LEAD: Duration = Duration::from_millis(50);
+// How often the paced backends wake up
+const TICK: Duration = Duration::from_millis(10);
+
+// Where preview audio goes
+#[derive(Clone, Debug, PartialEq)]
+pub enum PreviewBackend {
+    Native,          // The default output device (needs the `native-audio` feature)
+    Pipe(String),    // A player command reading a WAV stream on stdin
+    Null,            // Nowhere; playback still runs in real time
+    File(PathBuf),   // A WAV of exactly what would have been heard
+}
+
+impl Default for PreviewBackend {
+    fn default() -> PreviewBackend {
+        if cfg!(feature = "native-audio") {
+            PreviewBackend::Native
+        } else {
+            PreviewBackend::Pipe(DEFAULT_PIPE_COMMAND.to_string())
+        }
+    }
+}
+
+impl PreviewBackend {
+    // Parse `auto`, `native`, `pipe`, `pipe:<command>`, `null` or `file:<path>`
+    pub fn parse(spec: &str) -> Result<PreviewBackend, String> {
+        let (kind, value) = match spec.split_once(':') {
+            Some((kind, value)) => (kind.trim(), Some(value.trim())),
+            None => (spec.trim(), None),
+        };
+        match (kind.to_lowercase().as_str(), value) {
+            ("auto", None) => Ok(PreviewBackend::default()),
+            ("native", None) if cfg!(feature = "native-audio") => Ok(PreviewBackend::Native),
+            ("native", None) => Err("built without the native-audio feature".to_string()),
+            ("pipe", None) => Ok(PreviewBackend::Pipe(DEFAULT_PIPE_COMMAND.to_string())),
+            ("pipe", Some(command)) if !command.is_empty() => Ok(PreviewBackend::Pipe(command.to_string())),
+            ("null", None) => Ok(PreviewBackend::Null),
+            ("file", Some(path)) if !path.is_empty() => Ok(PreviewBackend::File(PathBuf::from(path))),
+            _ => Err(format!("unknown preview backend '{}' (expected auto, native, pipe[:command], null or file:path)", spec)),
+        }
+    }
+
+    // `preview.backend`, with `preview.command` as the pipe backend's player
+    pub fn from_config(config: &Config) -> Result<PreviewBackend> {
+        let backend = match config.get("preview.backend") {
+            Some(spec) => PreviewBackend::parse(spec).map_err(|e| ParasiteError::Config(format!("preview.backend: {}", e)))?,
+            None => PreviewBackend::default(),
+        };
+        Ok(match (backend, config.get("preview.command")) {
+            (PreviewBackend::Pipe(_), Some(command)) => PreviewBackend::Pipe(command.to_string()),
+            (backend, _) => backend,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PreviewBackend::Native => "native",
+            PreviewBackend::Pipe(_) => "pipe",
+            PreviewBackend::Null => "null",
+            PreviewBackend::File(_) => "file",
+        }
+    }
+}
+
+// What is playing and where it has got to, shared with the backend
+#[derive(Default)]
+struct Transport {
+    clip: Option<Arc<AudioBuffer>>, // The last clip played, kept after it ends so it can be scrubbed
+    position: usize, // Frames into the clip
+    playing: bool,
+    looping: bool,
+    generation: u64, // Bumped on every play and stop so backends can restart cleanly
+}
+
+impl Transport {
+    // Fill interleaved `out` from the clip, wrapping when looping and
+    // padding with silence once it ends. Returns the frames of clip used.
+    fn fill(&mut self, out: &mut [f32]) -> usize {
+        let Some(clip) = self.clip.clone().filter(|_| self.playing) else {
+            out.fill(0.0);
+            return 0;
+        };
+        let channels = clip.channels as usize;
+        let mut written = 0;
+        while written < out.len() {
+            if self.position >= clip.frames() {
+                if self.looping && clip.frames() > 0 {
+                    self.position = 0;
+                } else {
+                    self.playing = false;
+                    break;
+                }
+            }
+            let frames = (clip.frames() - self.position).min((out.len() - written) / channels);
+            let source = &clip.samples[self.position * channels..(self.position + frames) * channels];
+            out[written..written + source.len()].copy_from_slice(source);
+            written += source.len();
+            self.position += frames;
+        }
+        out[written..].fill(0.0);
+        written / channels
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+    pub position: Duration,
+    pub length: Duration,
+    pub playing: bool,
+}
+
+// Plays previews in the background. Starting a new preview replaces the old
+// one, so nothing overlaps and nothing is left running.
+pub struct Player {
+    transport: Arc<Mutex<Transport>>,
+    latency: Duration, // How far the transport runs ahead of what is heard
+    #[cfg(feature = "native-audio")]
+    native: Option<native::Output>,
+    _feeder: Option<Feeder>, // Thread driving the other backends
+}
+
+impl Player {
+    pub fn new(backend: &PreviewBackend) -> Result<Player> {
+        let transport = Arc::new(Mutex::new(Transport::default()));
+        let sink: Box<dyn Sink> = match backend {
+            #[cfg(feature = "native-audio")]
+            PreviewBackend::Native => {
+                let native = Some(native::Output::open(transport.clone())?);
+                return Ok(Player { transport, latency: Duration::ZERO, native, _feeder: None });
+            }
+            #[cfg(not(feature = "native-audio"))]
+            PreviewBackend::Native => {
+                return Err(ParasiteError::Config("built without the native-audio feature".to_string()).into())
+            }
+            PreviewBackend::Pipe(command) => Box::new(PipeSink::new(command)),
+            PreviewBackend::Null => Box::new(NullSink),
+            PreviewBackend::File(path) => Box::new(FileSink::new(path.clone())),
+        };
+        let feeder = Feeder::spawn(transport.clone(), sink);
+        Ok(Player {
+            transport,
+            latency: LEAD,
+            #[cfg(feature = "native-audio")]
+            native: None,
+            _feeder: Some(feeder),
+        })
+    }
+
+    // Play `clip` from the start, replacing whatever was playing
+    pub fn play(&self, clip: AudioBuffer, looping: bool) {
+        #[cfg(feature = "native-audio")]
+        let clip = match &self.native {
+            Some(output) => output.convert(clip),
+            None => clip,
+        };
+        let mut transport = self.lock();
+        transport.clip = Some(Arc::new(clip));
+        transport.position = 0;
+        transport.playing = true;
+        transport.looping = looping;
+        transport.generation += 1;
+    }
+
+    // Stop playing, keeping the clip and position to scrub from
+    pub fn stop(&self) {
+        let mut transport = self.lock();
+        if transport.playing {
+            // Leave the playhead where it was heard, not where the backend had got to
+            let rate = transport.clip.as_ref().map_or(0, |clip| clip.sample_rate);
+            let ahead = (self.latency.as_secs_f64() * rate as f64) as usize;
+            transport.position = transport.position.saturating_sub(ahead);
+        }
+        transport.playing = false;
+        transport.generation += 1;
+    }
+
+    // Jump to `position` in the current clip, as heard (see `progress`),
+    // playing from there if it had stopped. Returns false when there is no
+    // clip.
+    pub fn seek(&self, position: Duration) -> bool {
+        let mut transport = self.lock();
+        let Some(clip) = &transport.clip else {
+            return false;
+        };
+        // While playing, what is heard trails what has been fed by the latency
+        let position = if transport.playing { position + self.latency } else { position };
+        let frame = (position.as_secs_f64() * clip.sample_rate as f64) as usize;
+        let frames = clip.frames();
+        transport.position = if transport.looping && frames > 0 { frame % frames } else { frame.min(frames) };
+        if !transport.playing {
+            transport.playing = true;
+            transport.generation += 1;
+        }
+        true
+    }
+
+    pub fn set_looping(&self, looping: bool) {
+        self.lock().looping = looping;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.lock().playing
+    }
+
+    // Where playback is in the current clip, as heard
+    pub fn progress(&self) -> Option<Progress> {
+        let transport = self.lock();
+        let clip = transport.clip.as_ref()?;
+        let length = Duration::from_secs_f64(clip.frames() as f64 / clip.sample_rate as f64);
+        let position = Duration::from_secs_f64(transport.position as f64 / clip.sample_rate as f64);
+        let position = match (transport.playing, position.checked_sub(self.latency)) {
+            (false, _) => position,
+            (true, Some(heard)) => heard,
+            // Still hearing the end of the previous pass of a loop
+            (true, None) if transport.looping => length.saturating_sub(self.latency - position),
+            (true, None) => Duration::ZERO,
+        };
+        Some(Progress { position, length, playing: transport.playing })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Transport> {
+        self.transport.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// Output for backends that are fed from a thread at real-time pace
+trait Sink: Send {
+    // Begin a new stream in the clip's format
+    fn start(&mut self, sample_rate: u32, channels: u16) -> Result<()>;
+    fn write(&mut self, samples: &[f32]) -> Result<()>;
+    // End the stream, cutting off anything still buffered
+    fn stop(&mut self);
+    // End the stream once everything written has been heard
+    fn finish(&mut self) {
+        self.stop();
+    }
+}
+
+// Feeds a sink from the transport on a background thread, a little ahead of
+// real time, until dropped
+struct Feeder {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Feeder {
+    fn spawn(transport: Arc<Mutex<Transport>>, mut sink: Box<dyn Sink>) -> Feeder {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let running = running.clone();
+            std::thread::spawn(move || {
+                let mut generation = 0;
+                let mut started: Option<(Instant, u64)> = None; // Stream start and frames written since
+                let mut buffer = Vec::new();
+
+                while running.load(Ordering::Relaxed) {
+                    std::thread::sleep(TICK);
+                    let mut transport = transport.lock().unwrap_or_else(|e| e.into_inner());
+
+                    if transport.generation != generation {
+                        generation = transport.generation;
+                        sink.stop();
+                        started = None;
+                        if let Some(clip) = transport.clip.clone().filter(|_| transport.playing) {
+                            match sink.start(clip.sample_rate, clip.channels) {
+                                Ok(()) => started = Some((Instant::now(), 0)),
+                                Err(_) => transport.playing = false,
+                            }
+                        }
+                    }
+                    let clip = transport.clip.clone().filter(|_| transport.playing);
+                    let (Some(clip), Some((start, written))) = (clip, started.as_mut()) else {
+                        continue;
+                    };
+
+                    let due = ((start.elapsed() + LEAD).as_secs_f64() * clip.sample_rate as f64) as u64;
+                    let frames = due.saturating_sub(*written) as usize;
+                    if frames == 0 {
+                        continue;
+                    }
+                    buffer.resize(frames * clip.channels as usize, 0.0);
+                    let used = transport.fill(&mut buffer);
+                    let finished = !transport.playing;
+                    drop(transport);
+
+                    *written += frames as u64;
+                    if sink.write(&buffer[..used * clip.channels as usize]).is_err() {
+                        sink.stop();
+                        started = None;
+                    } else if finished {
+                        sink.finish();
+                        started = None;
+                    }
+                }
+                sink.stop();
+            })
+        };
+        Feeder { running, thread: Some(thread) }
+    }
+}
+
+impl Drop for Feeder {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct NullSink;
+
+impl Sink for NullSink {
+    fn start(&mut self, _sample_rate: u32, _channels: u16) -> Result<()> {
+        Ok(())
+    }
+
+    fn write(&mut self, _samples: &[f32]) -> Result<()> {
+        Ok(())
+    }
+
+    fn stop(&mut self) {}
+}
+
+// Streams 16-bit WAV into a player's stdin. The player is killed on stop, so
+// stopping is immediate and no process outlives its preview; at the end of
+// a clip its stdin is closed and it is left to play out and exit.
+struct PipeSink {
+    command: String,
+    child: Option<(Child, ChildStdin)>,
+    draining: Option<Child>, // A player finishing the last clip
+}
+
+impl PipeSink {
+    fn new(command: &str) -> PipeSink {
+        PipeSink { command: command.to_string(), child: None, draining: None }
+    }
+}
+
+impl Sink for PipeSink {
+    fn start(&mut self, sample_rate: u32, channels: u16) -> Result<()> {
+        let mut child = Command::new("sh")
+            .args(["-c", &self.command])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| ParasiteError::AudioProcessing(format!("cannot run `{}`: {}", self.command, e)))?;
+        let mut stdin = child.stdin.take().ok_or_else(|| ParasiteError::AudioProcessing("no stdin".to_string()))?;
+        stdin.write_all(&streaming_wav_header(sample_rate, channels))?;
+        self.child = Some((child, stdin));
+        Ok(())
+    }
+
+    fn write(&mut self, samples: &[f32]) -> Result<()> {
+        let Some((_, stdin)) = &mut self.child else {
+            return Ok(());
+        };
+        let bytes: Vec<u8> = samples.iter()
+            .flat_map(|s| ((s.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16).to_le_bytes())
+            .collect();
+        stdin.write_all(&bytes)?;
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        let players = self.child.take().map(|(child, _)| child).into_iter().chain(self.draining.take());
+        for mut player in players {
+            let _ = player.kill();
+            let _ = player.wait();
+        }
+    }
+
+    fn finish(&mut self) {
+        // Closing stdin lets the player reach the end of the stream
+        if let Some((child, stdin)) = self.child.take() {
+            drop(stdin);
+            self.draining = Some(child);
+        }
+    }
+}
+
+// A WAV header with the largest possible sizes, for a stream of unknown length
+fn streaming_wav_header(sample_rate: u32, channels: u16) -> Vec<u8> {
+    let block_align = channels * 2;
+    let mut header = Vec::with_capacity(44);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&u32::MAX.to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    header.extend_from_slice(&channels.to_le_bytes());
+    header.extend_from_slice(&sample_rate.to_le_bytes());
+    header.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    header.extend_from_slice(&block_align.to_le_bytes());
+    header.extend_from_slice(&16u16.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&(u32::MAX - 36).to_le_bytes());
+    header
+}
+
+// Writes each preview to a WAV file, replacing the previous one, for
+// checking playback without a sound card
+struct FileSink {
+    path: PathBuf,
+    writer: Option<hound::WavWriter<std::io::BufWriter<std::fs::File>>>,
+}
+
+impl FileSink {
+    fn new(path: PathBuf) -> FileSink {
+        FileSink { path, writer: None }
+    }
+}
+
+impl Sink for FileSink {
+    fn start(&mut self, sample_rate: u32, channels: u16) -> Result<()> {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        self.writer = Some(hound::WavWriter::create(&self.path, spec)?);
+        Ok(())
+    }
+
+    fn write(&mut self, samples: &[f32]) -> Result<()> {
+        if let Some(writer) = &mut self.writer {
+            for &sample in samples {
+                writer.write_sample(sample)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.finalize();
+        }
+    }
+}
+
+#[cfg(feature = "native-audio")]
+mod native {
+    use std::sync::{Arc, Mutex};
+
+    use anyhow::Result;
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use cpal::{FromSample, SampleFormat, SizedSample};
+
+    use super::Transport;
+    use crate::audio::AudioBuffer;
+    use crate::ParasiteError;
+
+    // The default output device, pulling from the transport in its callback
+    pub struct Output {
+        _stream: cpal::Stream,
+        sample_rate: u32,
+        channels: u16,
+    }
+
+    impl Output {
+        pub fn open(transport: Arc<Mutex<Transport>>) -> Result<Output> {
+            let device = cpal::default_host()
+                .default_output_device()
+                .ok_or_else(|| ParasiteError::AudioProcessing("no audio output device".to_string()))?;
+            let supported = device.default_output_config()
+                .map_err(|e| ParasiteError::AudioProcessing(format!("cannot open audio output: {}", e)))?;
+            let config = supported.config();
+            let stream = match supported.sample_format() {
+                SampleFormat::I16 => build::<i16>(&device, &config, transport)?,
+                SampleFormat::U16 => build::<u16>(&device, &config, transport)?,
+                _ => build::<f32>(&device, &config, transport)?,
+            };
+            stream.play().map_err(|e| ParasiteError::AudioProcessing(format!("cannot start audio output: {}", e)))?;
+            Ok(Output { _stream: stream, sample_rate: config.sample_rate.0, channels: config.channels })
+        }
+
+        // Bring a clip to the device's rate and channel count
+        pub fn convert(&self, clip: AudioBuffer) -> AudioBuffer {
+            let clip = if clip.channels == self.channels { clip } else { clip.to_mono().with_channels(self.channels) };
+            clip.resample(self.sample_rate)
+        }
+    }
+
+    fn build<T: SizedSample + FromSample<f32>>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        transport: Arc<Mutex<Transport>>,
+    ) -> Result<cpal::Stream> {
+        let mut buffer = Vec::new();
+        device
+            .build_output_stream(
+                config,
+                move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                    buffer.resize(data.len(), 0.0f32);
+                    transport.lock().unwrap_or_else(|e| e.into_inner()).fill(&mut buffer);
+                    for (out, &sample) in data.iter_mut().zip(&buffer) {
+                        *out = T::from_sample(sample);
+                    }
+                },
+                |_| {},
+                None,
+            )
+            .map_err(|e| ParasiteError::AudioProcessing(format!("cannot open audio output: {}", e)).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Mono clip whose samples count up, so positions can be read back from them
+    fn ramp(frames: usize, sample_rate: u32) -> AudioBuffer {
+        AudioBuffer {
+            sample_rate,
+            channels: 1,
+            samples: (0..frames).map(|i| i as f32 / frames as f32).collect(),
+        }
+    }
+
+    fn transport(frames: usize, looping: bool) -> Transport {
+        Transport { clip: Some(Arc::new(ramp(frames, 1000))), playing: true, looping, ..Transport::default() }
+    }
+
+    // Wait up to `limit` for the player to stop on its own
+    fn wait_until_stopped(player: &Player, limit: Duration) -> bool {
+        let started = Instant::now();
+        while player.is_playing() {
+            if started.elapsed() > limit {
+                return false;
+            }
+            std::thread::sleep(TICK);
+        }
+        true
+    }
+
+    fn read_frames(path: &std::path::Path) -> Vec<f32> {
+        hound::WavReader::open(path).unwrap().samples::<f32>().map(Result::unwrap).collect()
+    }
+
+    fn temp_wav(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("parasite-{}-{}.wav", name, std::process::id()))
+    }
+
+    #[test]
+    fn fill_stops_at_the_end_of_the_clip() {
+        let mut transport = transport(10, false);
+        let mut out = vec![1.0; 6];
+        assert_eq!(transport.fill(&mut out), 6);
+        assert!(transport.playing);
+
+        assert_eq!(transport.fill(&mut out), 4);
+        assert_eq!(out[..4], [0.6, 0.7, 0.8, 0.9]);
+        assert_eq!(out[4..], [0.0, 0.0]);
+        assert!(!transport.playing);
+
+        assert_eq!(transport.fill(&mut out), 0);
+        assert!(out.iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn fill_wraps_when_looping() {
+        let mut transport = transport(4, true);
+        let mut out = vec![0.0; 10];
+        assert_eq!(transport.fill(&mut out), 10);
+        assert_eq!(out, [0.0, 0.25, 0.5, 0.75, 0.0, 0.25, 0.5, 0.75, 0.0, 0.25]);
+        assert_eq!(transport.position, 2);
+        assert!(transport.playing);
+    }
+
+    #[test]
+    fn fill_is_silent_when_stopped() {
+        let mut transport = transport(4, false);
+        transport.playing = false;
+        let mut out = vec![1.0; 3];
+        assert_eq!(transport.fill(&mut out), 0);
+        assert_eq!(out, [0.0; 3]);
+    }
+
+    #[test]
+    fn file_backend_records_the_whole_clip() {
+        let path = temp_wav("play");
+        let clip = ramp(4800, 48000);
+        let player = Player::new(&PreviewBackend::File(path.clone())).unwrap();
+        player.play(clip.clone(), false);
+        assert!(wait_until_stopped(&player, Duration::from_secs(2)));
+        drop(player);
+
+        assert_eq!(read_frames(&path), clip.samples);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn stop_cuts_the_clip_short_and_replay_starts_over() {
+        let path = temp_wav("stop");
+        let clip = ramp(48000, 48000);
+        let player = Player::new(&PreviewBackend::File(path.clone())).unwrap();
+        player.play(clip.clone(), false);
+        std::thread::sleep(Duration::from_millis(150));
+        player.stop();
+        assert!(!player.is_playing());
+        let progress = player.progress().unwrap();
+        assert!(!progress.playing);
+        assert!(progress.position < progress.length);
+
+        // Give the feeder a tick to close the file
+        std::thread::sleep(TICK * 3);
+        let recorded = read_frames(&path);
+        assert!(!recorded.is_empty() && recorded.len() < clip.samples.len());
+        assert_eq!(recorded[..], clip.samples[..recorded.len()]);
+
+        // Replaying writes the clip again from the start
+        let short = ramp(2400, 48000);
+        player.play(short.clone(), false);
+        assert!(wait_until_stopped(&player, Duration::from_secs(2)));
+        drop(player);
+        assert_eq!(read_frames(&path), short.samples);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn looping_continues_until_turned_off() {
+        let player = Player::new(&PreviewBackend::Null).unwrap();
+        player.play(ramp(4800, 48000), true);
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(player.is_playing());
+        let progress = player.progress().unwrap();
+        assert!(progress.position <= progress.length);
+
+        player.set_looping(false);
+        assert!(wait_until_stopped(&player, Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn seek_lands_on_the_heard_position() {
+        let player = Player::new(&PreviewBackend::Null).unwrap();
+        assert!(!player.seek(Duration::ZERO));
+
+        player.play(ramp(48000, 48000), false);
+        std::thread::sleep(Duration::from_millis(200));
+        let before = player.progress().unwrap().position;
+        assert!(player.seek(before + Duration::from_millis(25)));
+        let after = player.progress().unwrap().position;
+        // Allow for rounding to a frame and the playhead moving between the reads
+        assert!(after >= before + Duration::from_millis(24) && after < before + Duration::from_millis(45), "{:?} -> {:?}", before, after);
+    }
+
+    #[test]
+    fn parses_backend_specs() {
+        assert_eq!(PreviewBackend::parse("null"), Ok(PreviewBackend::Null));
+        assert_eq!(PreviewBackend::parse("file:/tmp/out.wav"), Ok(PreviewBackend::File(PathBuf::from("/tmp/out.wav"))));
+        assert_eq!(PreviewBackend::parse("pipe: aplay -q"), Ok(PreviewBackend::Pipe("aplay -q".to_string())));
+        assert_eq!(PreviewBackend::parse("pipe"), Ok(PreviewBackend::Pipe(DEFAULT_PIPE_COMMAND.to_string())));
+        assert!(PreviewBackend::parse("file:").is_err());
+        assert!(PreviewBackend::parse("speaker").is_err());
+    }
+}
@@ -0,0 +1,398 @@
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::audio::{AudioBuffer, Biquad};
+use crate::config::Config;
+use crate::ParasiteError;
+
+// DC blocker corner frequency
+const DC_CUTOFF_HZ: f64 = 5.0;
+// Gate detector and opening times
+const GATE_DETECT: Duration = Duration::from_millis(10);
+const GATE_OPEN: Duration = Duration::from_millis(1);
+// Declick analysis block, the longest run repaired as a click, and the
+// smallest deviation from the neighbours that can count as one
+const DECLICK_BLOCK: usize = 1024;
+const DECLICK_MAX_RUN: usize = 16;
+const DECLICK_FLOOR: f32 = 0.02;
+
+// Chains available without any configuration; config presets of the same
+// name replace them
+const BUILT_IN_PRESETS: [(&str, &str); 2] = [
+    ("clean", "dc, highpass 60, declick"),
+    ("voice", "dc, highpass 80, gate -55, eq 3000 2 1, compress -20 3, gain 2"),
+];
+
+// One processing stage. Written in presets as the step name followed by its
+// numeric arguments; bracketed ones are optional:
+//   dc                                  DC offset removal
+//   highpass <hz> [q]                   High-pass filter (rumble, handling noise)
+//   gate <threshold dB> [release ms]    Noise gate
+//   eq <hz> <gain dB> [q]               Peaking EQ band
+//   lowshelf <hz> <gain dB>             Low shelf
+//   highshelf <hz> <gain dB>            High shelf
+//   compress <threshold dB> <ratio> [attack ms] [release ms]
+//   declick [sensitivity]               Repair short clicks and pops
+//   gain <dB>                           Fixed gain
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step {
+    DcRemoval,
+    HighPass { frequency: f64, q: f64 },
+    Gate { threshold_db: f32, release: Duration },
+    Peak { frequency: f64, gain_db: f64, q: f64 },
+    LowShelf { frequency: f64, gain_db: f64 },
+    HighShelf { frequency: f64, gain_db: f64 },
+    Compress { threshold_db: f32, ratio: f32, attack: Duration, release: Duration },
+    Declick { sensitivity: f32 },
+    Gain { db: f32 },
+}
+
+impl Step {
+    fn parse(spec: &str) -> Result<Step, String> {
+        let mut words = spec.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let args = words
+            .map(|w| w.parse::<f64>().map_err(|_| invalid(spec, &format!("'{}' is not a number", w))))
+            .collect::<Result<Vec<f64>, String>>()?;
+
+        // Argument `i`, falling back to `default` when it's optional
+        let arg = |i: usize, default: Option<f64>| -> Result<f64, String> {
+            args.get(i).copied().or(default).ok_or_else(|| invalid(spec, "missing argument"))
+        };
+        let ms = |value: f64| Duration::from_secs_f64(value.max(0.0) / 1000.0);
+
+        let (step, max_args) = match name {
+            "dc" => (Step::DcRemoval, 0),
+            "highpass" => (Step::HighPass { frequency: arg(0, None)?, q: arg(1, Some(std::f64::consts::FRAC_1_SQRT_2))? }, 2),
+            "gate" => (Step::Gate { threshold_db: arg(0, None)? as f32, release: ms(arg(1, Some(80.0))?) }, 2),
+            "eq" => (Step::Peak { frequency: arg(0, None)?, gain_db: arg(1, None)?, q: arg(2, Some(1.0))? }, 3),
+            "lowshelf" => (Step::LowShelf { frequency: arg(0, None)?, gain_db: arg(1, None)? }, 2),
+            "highshelf" => (Step::HighShelf { frequency: arg(0, None)?, gain_db: arg(1, None)? }, 2),
+            "compress" => (
+                Step::Compress {
+                    threshold_db: arg(0, None)? as f32,
+                    ratio: (arg(1, None)? as f32).max(1.0),
+                    attack: ms(arg(2, Some(5.0))?),
+                    release: ms(arg(3, Some(100.0))?),
+                },
+                4,
+            ),
+            "declick" => (Step::Declick { sensitivity: arg(0, Some(8.0))? as f32 }, 1),
+            "gain" => (Step::Gain { db: arg(0, None)? as f32 }, 1),
+            _ => return Err(invalid(spec, "unknown step")),
+        };
+
+        if args.len() > max_args {
+            return Err(invalid(spec, "too many arguments"));
+        }
+        if matches!(step, Step::HighPass { q, .. } | Step::Peak { q, .. } if q <= 0.0) {
+            return Err(invalid(spec, "q must be positive"));
+        }
+        Ok(step)
+    }
+
+    fn apply(&self, buffer: &mut AudioBuffer) {
+        let rate = buffer.sample_rate;
+        match *self {
+            Step::DcRemoval => {
+                let r = 1.0 - 2.0 * std::f64::consts::PI * DC_CUTOFF_HZ / rate as f64;
+                for_each_channel(buffer, |samples| {
+                    let (mut x1, mut y1) = (0.0f64, 0.0f64);
+                    for s in samples.iter_mut() {
+                        let x = *s as f64;
+                        y1 = x - x1 + r * y1;
+                        x1 = x;
+                        *s = y1 as f32;
+                    }
+                });
+            }
+            Step::HighPass { frequency, q } => filter(buffer, || Biquad::high_pass(rate, frequency, q)),
+            Step::Peak { frequency, gain_db, q } => filter(buffer, || Biquad::peaking(rate, frequency, gain_db, q)),
+            Step::LowShelf { frequency, gain_db } => filter(buffer, || Biquad::low_shelf(rate, frequency, gain_db)),
+            Step::HighShelf { frequency, gain_db } => filter(buffer, || Biquad::high_shelf(rate, frequency, gain_db)),
+            Step::Gate { threshold_db, release } => gate(buffer, threshold_db, release),
+            Step::Compress { threshold_db, ratio, attack, release } => compress(buffer, threshold_db, ratio, attack, release),
+            Step::Declick { sensitivity } => for_each_channel(buffer, |samples| declick(samples, sensitivity)),
+            Step::Gain { db } => {
+                let gain = 10f32.powf(db / 20.0);
+                for s in &mut buffer.samples {
+                    *s *= gain;
+                }
+            }
+        }
+    }
+}
+
+// A named, ordered list of steps applied between slicing and writing
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcessingChain {
+    pub name: String,
+    pub steps: Vec<Step>,
+}
+
+impl ProcessingChain {
+    // Parse comma-separated steps, e.g. `dc, highpass 80, compress -20 3`
+    pub fn parse(name: &str, spec: &str) -> Result<ProcessingChain> {
+        let steps = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(Step::parse)
+            .collect::<Result<Vec<Step>, String>>()
+            .map_err(|e| ParasiteError::Config(format!("preset '{}': {}", name, e)))?;
+        Ok(ProcessingChain { name: name.to_string(), steps })
+    }
+
+    pub fn apply(&self, buffer: &mut AudioBuffer) {
+        for step in &self.steps {
+            step.apply(buffer);
+        }
+    }
+}
+
+// Built-in presets plus any `[process.<name>]` sections with a `steps` key,
+// sorted by name
+pub fn presets(config: &Config) -> Result<Vec<ProcessingChain>> {
+    let mut presets = BUILT_IN_PRESETS
+        .iter()
+        .map(|(name, spec)| ProcessingChain::parse(name, spec))
+        .collect::<Result<Vec<ProcessingChain>>>()?;
+
+    for name in config.subsections("process") {
+        let Some(spec) = config.get(&format!("process.{}.steps", name)) else {
+            continue;
+        };
+        let chain = ProcessingChain::parse(&name, spec)?;
+        presets.retain(|p| p.name != chain.name);
+        presets.push(chain);
+    }
+
+    presets.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(presets)
+}
+
+// The preset called `name`; `off` and `none` select no processing
+pub fn find(presets: &[ProcessingChain], name: &str) -> Result<Option<ProcessingChain>> {
+    if name == "off" || name == "none" {
+        return Ok(None);
+    }
+    match presets.iter().find(|p| p.name == name) {
+        Some(preset) => Ok(Some(preset.clone())),
+        None => {
+            let names: Vec<&str> = presets.iter().map(|p| p.name.as_str()).collect();
+            Err(ParasiteError::Config(format!("unknown processing preset '{}' (available: {})", name, names.join(", "))).into())
+        }
+    }
+}
+
+// Per-sample choice of chain; `Default` follows the session's preset
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ChainChoice {
+    #[default]
+    Default,
+    Off,
+    Preset(String),
+}
+
+impl ChainChoice {
+    pub fn is_set(&self) -> bool {
+        *self != ChainChoice::Default
+    }
+
+    pub fn resolve(&self, default: Option<&ProcessingChain>, presets: &[ProcessingChain]) -> Option<ProcessingChain> {
+        match self {
+            ChainChoice::Default => default.cloned(),
+            ChainChoice::Off => None,
+            ChainChoice::Preset(name) => presets.iter().find(|p| &p.name == name).cloned(),
+        }
+    }
+
+    // Step through default, off and each preset
+    pub fn cycle(&mut self, presets: &[ProcessingChain]) {
+        let next = |i: usize| presets.get(i).map(|p| ChainChoice::Preset(p.name.clone())).unwrap_or_default();
+        *self = match &*self {
+            ChainChoice::Default => ChainChoice::Off,
+            ChainChoice::Off => next(0),
+            ChainChoice::Preset(name) => match presets.iter().position(|p| &p.name == name) {
+                Some(i) => next(i + 1),
+                None => ChainChoice::Default,
+            },
+        };
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            ChainChoice::Default => "processing default".to_string(),
+            ChainChoice::Off => "processing off".to_string(),
+            ChainChoice::Preset(name) => format!("processing '{}'", name),
+        }
+    }
+}
+
+fn invalid(spec: &str, reason: &str) -> String {
+    format!("invalid step '{}': {}", spec, reason)
+}
+
+// Run `process` over each channel's samples in turn
+fn for_each_channel(buffer: &mut AudioBuffer, mut process: impl FnMut(&mut [f32])) {
+    let channels = buffer.channels as usize;
+    for c in 0..channels {
+        let mut samples: Vec<f32> = buffer.samples.iter().skip(c).step_by(channels).copied().collect();
+        process(&mut samples);
+        for (i, s) in samples.into_iter().enumerate() {
+            buffer.samples[i * channels + c] = s;
+        }
+    }
+}
+
+// Run a fresh biquad from `make` over each channel
+fn filter(buffer: &mut AudioBuffer, make: impl Fn() -> Biquad) {
+    for_each_channel(buffer, |samples| {
+        let mut biquad = make();
+        for s in samples.iter_mut() {
+            *s = biquad.process(*s as f64) as f32;
+        }
+    });
+}
+
+// One-pole smoothing coefficient for a time constant
+fn coefficient(time: Duration, sample_rate: u32) -> f32 {
+    let samples = time.as_secs_f32() * sample_rate as f32;
+    if samples < 1.0 { 0.0 } else { (-1.0 / samples).exp() }
+}
+
+// Peak level of each frame across all channels, so stereo stays linked
+fn frame_peaks(buffer: &AudioBuffer) -> Vec<f32> {
+    buffer.samples
+        .chunks(buffer.channels.max(1) as usize)
+        .map(|frame| frame.iter().fold(0.0f32, |peak, s| peak.max(s.abs())))
+        .collect()
+}
+
+fn apply_gains(buffer: &mut AudioBuffer, gains: &[f32]) {
+    for (frame, &gain) in buffer.samples.chunks_mut(buffer.channels.max(1) as usize).zip(gains) {
+        for s in frame {
+            *s *= gain;
+        }
+    }
+}
+
+// Silence the signal while its envelope stays under the threshold, opening
+// quickly and closing over `release`
+fn gate(buffer: &mut AudioBuffer, threshold_db: f32, release: Duration) {
+    let threshold = 10f32.powf(threshold_db / 20.0);
+    let detect = coefficient(GATE_DETECT, buffer.sample_rate);
+    let open = coefficient(GATE_OPEN, buffer.sample_rate);
+    let close = coefficient(release, buffer.sample_rate);
+
+    let (mut envelope, mut gain) = (0.0f32, 0.0f32);
+    let gains: Vec<f32> = frame_peaks(buffer)
+        .into_iter()
+        .map(|peak| {
+            envelope = peak.max(envelope * detect);
+            let (target, coef) = if envelope > threshold { (1.0, open) } else { (0.0, close) };
+            gain = target + (gain - target) * coef;
+            gain
+        })
+        .collect();
+    apply_gains(buffer, &gains);
+}
+
+// Feed-forward peak compressor with a hard knee
+fn compress(buffer: &mut AudioBuffer, threshold_db: f32, ratio: f32, attack: Duration, release: Duration) {
+    let attack = coefficient(attack, buffer.sample_rate);
+    let release = coefficient(release, buffer.sample_rate);
+
+    let mut envelope = 0.0f32;
+    let gains: Vec<f32> = frame_peaks(buffer)
+        .into_iter()
+        .map(|peak| {
+            let coef = if peak > envelope { attack } else { release };
+            envelope = peak + (envelope - peak) * coef;
+            let level_db = 20.0 * envelope.max(1e-10).log10();
+            if level_db > threshold_db {
+                10f32.powf((threshold_db - level_db) * (1.0 - 1.0 / ratio) / 20.0)
+            } else {
+                1.0
+            }
+        })
+        .collect();
+    apply_gains(buffer, &gains);
+}
+
+// Replace short runs of samples that jump away from their neighbours far more
+// than is typical for the surrounding audio with a straight line across them
+fn declick(samples: &mut [f32], sensitivity: f32) {
+    if samples.len() < 3 {
+        return;
+    }
+
+    // Deviation of each inner sample from the midpoint of its neighbours;
+    // entry `r` belongs to sample `r + 1`
+    let residual: Vec<f32> = samples
+        .windows(3)
+        .map(|w| (w[1] - (w[0] + w[2]) / 2.0).abs())
+        .collect();
+
+    for block in (0..residual.len()).step_by(DECLICK_BLOCK) {
+        let end = (block + DECLICK_BLOCK).min(residual.len());
+        let mut sorted = residual[block..end].to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let threshold = (sorted[sorted.len() / 2] * sensitivity).max(DECLICK_FLOOR);
+
+        let mut r = block;
+        while r < end {
+            if residual[r] <= threshold {
+                r += 1;
+                continue;
+            }
+            let run_start = r;
+            while r < end && residual[r] > threshold {
+                r += 1;
+            }
+
+            // Samples `first..=last` are damaged; both neighbours are intact
+            let (first, last) = (run_start + 1, r);
+            if last - first < DECLICK_MAX_RUN {
+                let (before, after) = (samples[first - 1], samples[last + 1]);
+                let span = (last + 2 - first) as f32;
+                for (k, s) in samples[first..=last].iter_mut().enumerate() {
+                    *s = before + (after - before) * (k + 1) as f32 / span;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_steps_with_defaults() {
+        assert_eq!(Step::parse("dc"), Ok(Step::DcRemoval));
+        assert_eq!(
+            Step::parse("highpass 80"),
+            Ok(Step::HighPass { frequency: 80.0, q: std::f64::consts::FRAC_1_SQRT_2 })
+        );
+        assert_eq!(
+            Step::parse("compress -20 3"),
+            Ok(Step::Compress {
+                threshold_db: -20.0,
+                ratio: 3.0,
+                attack: Duration::from_millis(5),
+                release: Duration::from_millis(100),
+            })
+        );
+        assert_eq!(Step::parse("gate -55 40"), Ok(Step::Gate { threshold_db: -55.0, release: Duration::from_millis(40) }));
+        assert_eq!(Step::parse("eq 3000 2 1.5"), Ok(Step::Peak { frequency: 3000.0, gain_db: 2.0, q: 1.5 }));
+    }
+
+    #[test]
+    fn rejects_bad_steps() {
+        for spec in ["", "reverb", "gain", "gain loud", "dc 1", "lowshelf 100 3 1", "highpass 80 0", "eq 1000 3 -1"] {
+            assert!(Step::parse(spec).is_err(), "{:?} should not parse", spec);
+        }
+    }
+}
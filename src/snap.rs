@@ -0,0 +1,208 @@
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::audio::{self, AudioBuffer};
+use crate::config::Config;
+
+// Analysis hop and frame length
+const HOP: Duration = Duration::from_millis(5);
+const FRAME: Duration = Duration::from_millis(20);
+// Speech must stay active this many hops to count as an onset (not a click)
+const MIN_ACTIVE_HOPS: usize = 4;
+// Spectral flux must exceed the median by this factor to mark an onset
+const FLUX_FACTOR: f32 = 3.0;
+// Quiet frames still count as active on a flux onset if within this many dB
+// of the threshold (soft consonants like "s" and "f")
+const FLUX_HEADROOM_DB: f32 = 15.0;
+
+pub const DEFAULT_THRESHOLD_DB: f32 = -45.0;
+pub const DEFAULT_WINDOW_MS: u64 = 300;
+
+#[derive(Clone, Copy, Debug)]
+pub struct SnapOptions {
+    pub threshold_db: f32, // RMS level (dBFS) separating speech from silence
+    pub window: Duration,  // How far beyond the cue boundaries to look
+}
+
+impl Default for SnapOptions {
+    fn default() -> SnapOptions {
+        SnapOptions {
+            threshold_db: DEFAULT_THRESHOLD_DB,
+            window: Duration::from_millis(DEFAULT_WINDOW_MS),
+        }
+    }
+}
+
+impl SnapOptions {
+    // Settings from the `[snap]` section: `threshold_db` and `window_ms`
+    pub fn from_config(config: &Config) -> Result<SnapOptions> {
+        let defaults = SnapOptions::default();
+        Ok(SnapOptions {
+            threshold_db: config.get_f32("snap.threshold_db")?.unwrap_or(defaults.threshold_db),
+            window: config.get_ms("snap.window_ms")?.unwrap_or(defaults.window),
+        })
+    }
+}
+
+// Move a cue's boundaries to the actual speech onset and offset in `source`.
+// Activity is judged per frame from the RMS envelope against the silence
+// threshold, with spectral flux onsets catching quiet consonants. A boundary
+// whose speech runs straight into the edge of the search window (no silence
+// to snap to) is left where it was, as is a cue with no speech at all.
+pub fn snap(source: &Path, start: Duration, end: Duration, options: &SnapOptions) -> Result<(Duration, Duration)> {
+    let window_start = start.saturating_sub(options.window);
+    let buffer = AudioBuffer::read_wav_range(source, window_start, end + options.window)?.to_mono();
+
+    let hop = ((HOP.as_secs_f64() * buffer.sample_rate as f64).round() as usize).max(1);
+    let frame = ((FRAME.as_secs_f64() * buffer.sample_rate as f64).round() as usize).max(hop);
+    let active = activity(&buffer.samples, frame, hop, options.threshold_db);
+    if active.is_empty() {
+        return Ok((start, end));
+    }
+
+    let hop_secs = hop as f64 / buffer.sample_rate as f64;
+    let to_hop = |time: Duration| {
+        let offset = time.saturating_sub(window_start).as_secs_f64();
+        ((offset / hop_secs) as usize).min(active.len() - 1)
+    };
+    let to_time = |index: usize| window_start + Duration::from_secs_f64(index as f64 * hop_secs);
+    let cue_start = to_hop(start);
+    let cue_end = to_hop(end);
+
+    // Speech inside the cue anchors the search in each direction
+    let Some(first) = (cue_start..=cue_end).find(|&i| onset_at(&active, i)) else {
+        return Ok((start, end));
+    };
+    let Some(last) = (cue_start..=cue_end).rev().find(|&i| offset_at(&active, i)) else {
+        return Ok((start, end));
+    };
+
+    // Walk outwards while speech continues, stopping at the first silence
+    let onset = (0..=first).rev().take_while(|&i| active[i]).last().unwrap_or(first);
+    let offset = (last..active.len()).take_while(|&i| active[i]).last().unwrap_or(last);
+
+    // Frames cover `frame` samples from their hop, so the earliest and
+    // latest edges of the active frames keep the whole of the speech
+    let frame_length = Duration::from_secs_f64(frame as f64 / buffer.sample_rate as f64);
+    let snapped_start = if onset == 0 { start } else { to_time(onset) };
+    let snapped_end = if offset == active.len() - 1 { end } else { to_time(offset) + frame_length };
+
+    if snapped_end <= snapped_start {
+        return Ok((start, end));
+    }
+    Ok((snapped_start, snapped_end))
+}
+
+// Whether speech starts at hop `i` and stays active long enough to count
+fn onset_at(active: &[bool], i: usize) -> bool {
+    active.len() >= i + MIN_ACTIVE_HOPS && active[i..i + MIN_ACTIVE_HOPS].iter().all(|&a| a)
+}
+
+// Whether speech ends at hop `i` after being active long enough to count
+fn offset_at(active: &[bool], i: usize) -> bool {
+    i + 1 >= MIN_ACTIVE_HOPS && active[i + 1 - MIN_ACTIVE_HOPS..=i].iter().all(|&a| a)
+}
+
+// Per-hop speech activity from the RMS envelope and spectral flux
+fn activity(samples: &[f32], frame: usize, hop: usize, threshold_db: f32) -> Vec<bool> {
+    if samples.len() < frame {
+        return Vec::new();
+    }
+
+    let mut levels = Vec::new();
+    let mut flux = Vec::new();
+    let mut previous: Option<Vec<f32>> = None;
+    for start in (0..=samples.len() - frame).step_by(hop) {
+        let window = &samples[start..start + frame];
+        let rms = (window.iter().map(|s| s * s).sum::<f32>() / frame as f32).sqrt();
+        levels.push(20.0 * rms.max(1e-10).log10());
+
+        // Positive change in magnitude across the spectrum since the last frame
+        let spectrum = audio::magnitude_spectrum(window);
+        let change = match &previous {
+            Some(prev) => spectrum.iter().zip(prev).map(|(m, p)| (m - p).max(0.0)).sum(),
+            None => 0.0,
+        };
+        flux.push(change);
+        previous = Some(spectrum);
+    }
+
+    let mut sorted = flux.clone();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let flux_threshold = sorted[sorted.len() / 2] * FLUX_FACTOR;
+
+    levels.iter()
+        .zip(&flux)
+        .map(|(&level, &change)| {
+            level > threshold_db
+                || (change > flux_threshold && level > threshold_db - FLUX_HEADROOM_DB)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    // Write a mono recording that's silent except for a 440Hz tone between
+    // `from` and `to` seconds
+    fn recording(name: &str, seconds: f64, from: f64, to: f64) -> std::path::PathBuf {
+        let samples = (0..(seconds * RATE as f64) as usize)
+            .map(|i| {
+                let t = i as f64 / RATE as f64;
+                if (from..to).contains(&t) { 0.3 * (std::f64::consts::TAU * 440.0 * t).sin() as f32 } else { 0.0 }
+            })
+            .collect();
+        let path = std::env::temp_dir().join(format!("parasite-snap-{}-{}.wav", name, std::process::id()));
+        AudioBuffer { sample_rate: RATE, channels: 1, samples }.write_wav(&path, 16, false).unwrap();
+        path
+    }
+
+    fn seconds(time: Duration) -> f64 {
+        time.as_secs_f64()
+    }
+
+    #[test]
+    fn snaps_loose_boundaries_to_the_speech() {
+        let path = recording("loose", 2.5, 1.0, 1.5);
+        let (start, end) = snap(&path, Duration::from_millis(800), Duration::from_millis(1800), &SnapOptions::default()).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        // Within a frame of the tone's edges, keeping all of it
+        assert!((0.975..=1.0).contains(&seconds(start)), "start {:?}", start);
+        assert!((1.5..=1.525).contains(&seconds(end)), "end {:?}", end);
+    }
+
+    #[test]
+    fn tight_boundaries_widen_to_the_speech() {
+        let path = recording("tight", 2.5, 1.0, 1.5);
+        let (start, end) = snap(&path, Duration::from_millis(1100), Duration::from_millis(1400), &SnapOptions::default()).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert!((0.975..=1.0).contains(&seconds(start)), "start {:?}", start);
+        assert!((1.5..=1.525).contains(&seconds(end)), "end {:?}", end);
+    }
+
+    #[test]
+    fn a_cue_without_speech_is_left_alone() {
+        let path = recording("silent", 2.5, 0.0, 0.0);
+        let cue = (Duration::from_millis(800), Duration::from_millis(1800));
+        assert_eq!(snap(&path, cue.0, cue.1, &SnapOptions::default()).unwrap(), cue);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn speech_running_past_the_window_keeps_that_boundary() {
+        // Speech from before the window opens to after the cue ends
+        let path = recording("running", 2.5, 0.2, 1.5);
+        let (start, end) = snap(&path, Duration::from_millis(800), Duration::from_millis(1300), &SnapOptions::default()).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(start, Duration::from_millis(800));
+        assert!((1.5..=1.525).contains(&seconds(end)), "end {:?}", end);
+    }
+}
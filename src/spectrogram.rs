@@ -0,0 +1,98 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+use ratatui::style::Color;
+
+use crate::audio::{self, AudioBuffer};
+
+// Length of each analysis frame; short enough to place a plosive's burst
+const FRAME: Duration = Duration::from_millis(16);
+// Highest frequency shown; sibilants sit between about 4 and 12kHz
+const MAX_FREQUENCY: f32 = 16000.0;
+// Levels shown below the loudest point in view
+const DYNAMIC_RANGE_DB: f32 = 80.0;
+
+// xterm-256 colours from silence to loudest: black, blues, magentas, reds,
+// yellows, white
+const HEAT: &[u8] = &[16, 17, 18, 19, 20, 21, 57, 93, 129, 165, 201, 200, 199, 198, 197, 196, 202, 208, 214, 220, 226, 227, 229, 231];
+
+// Short-time spectrum of the stretch of a recording on screen, one analysis
+// frame per column and linear frequency bands per row (lowest first)
+pub struct Spectrogram {
+    pub source: PathBuf,
+    pub start: Duration, // View in transcript time
+    pub end: Duration,
+    pub columns: usize,
+    pub rows: usize,
+    pub top_frequency: f32,
+    levels: Vec<f32>, // 0.0 (floor) to 1.0 (loudest), row-major from the lowest band
+}
+
+impl Spectrogram {
+    // Analyse `source` between `from` and `to` (its own time, which differs
+    // from the view's for a master) for a view of `start`..`end`
+    pub fn compute(
+        source: &Path,
+        (start, end): (Duration, Duration),
+        (from, to): (Duration, Duration),
+        columns: usize,
+        rows: usize,
+    ) -> Result<Spectrogram> {
+        let rate = hound::WavReader::open(source).map(|r| r.spec().sample_rate).unwrap_or(16000);
+        let size = ((FRAME.as_secs_f64() * rate as f64) as usize).next_power_of_two().max(64);
+        let half = Duration::from_secs_f64(size as f64 / 2.0 / rate as f64);
+
+        // Read half a frame either side so edge columns are centred too
+        let read_from = from.saturating_sub(half);
+        let buffer = AudioBuffer::read_wav_range(source, read_from, to + half)?.to_mono();
+        let offset = ((from - read_from).as_secs_f64() * rate as f64) as usize;
+        let span = (to.saturating_sub(from)).as_secs_f64() * rate as f64;
+
+        let nyquist = rate as f32 / 2.0;
+        let top_frequency = nyquist.min(MAX_FREQUENCY);
+        let bins_shown = ((top_frequency / nyquist) * (size / 2) as f32) as usize;
+
+        let mut decibels = vec![f32::NEG_INFINITY; columns * rows];
+        let mut frame = vec![0.0f32; size];
+        for column in 0..columns {
+            // Frame centred on the column's time, silent past either end
+            let centre = offset + ((column as f64 + 0.5) / columns as f64 * span) as usize;
+            for (i, sample) in frame.iter_mut().enumerate() {
+                let index = (centre + i).checked_sub(size / 2);
+                *sample = index.and_then(|index| buffer.samples.get(index)).copied().unwrap_or(0.0);
+            }
+            let spectrum = audio::magnitude_spectrum(&frame);
+
+            // Each row takes the strongest bin in its band
+            for row in 0..rows {
+                let first = row * bins_shown / rows;
+                let last = ((row + 1) * bins_shown / rows).max(first + 1);
+                let power = spectrum[first..last.min(spectrum.len())]
+                    .iter()
+                    .fold(0.0f32, |power, magnitude| power.max(magnitude * magnitude));
+                decibels[row * columns + column] = 10.0 * (power + 1e-20).log10();
+            }
+        }
+
+        let loudest = decibels.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let levels = decibels.iter()
+            .map(|db| ((db - (loudest - DYNAMIC_RANGE_DB)) / DYNAMIC_RANGE_DB).clamp(0.0, 1.0))
+            .collect();
+        Ok(Spectrogram { source: source.to_path_buf(), start, end, columns, rows, top_frequency, levels })
+    }
+
+    pub fn shows(&self, source: &Path, start: Duration, end: Duration, columns: usize, rows: usize) -> bool {
+        self.source == source && self.start == start && self.end == end && self.columns == columns && self.rows == rows
+    }
+
+    pub fn level(&self, column: usize, row: usize) -> f32 {
+        self.levels.get(row * self.columns + column).copied().unwrap_or(0.0)
+    }
+}
+
+// Colour for a level from `Spectrogram::level`
+pub fn heat(level: f32) -> Color {
+    let index = (level * (HEAT.len() - 1) as f32).round() as usize;
+    Color::Indexed(HEAT[index.min(HEAT.len() - 1)])
+}
@@ -0,0 +1,153 @@
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::audio::AudioBuffer;
+use crate::fade::{self, FadeCurve};
+use crate::ParasiteError;
+
+// Defaults used by the TUI and the `supercut` subcommand
+pub const DEFAULT_GAP_MS: u64 = 250;
+pub const DEFAULT_CROSSFADE_MS: u64 = 20;
+
+pub struct SupercutOptions {
+    pub gap: Duration,       // Silence between segments (before crossfade overlap)
+    pub crossfade: Duration, // Fade length at each segment edge; overlaps neighbours
+}
+
+// A stretch of a source recording to include in the montage
+pub struct Segment {
+    pub source: PathBuf, // Source WAV
+    pub text: String,
+    pub start: Duration,
+    pub end: Duration,
+}
+
+// A segment's position in the rendered montage
+struct Cue {
+    start: Duration,
+    end: Duration,
+    label: String,
+}
+
+// Render segments back to back into `<dir>/<name>.wav`, with `<name>.vtt` and
+// `<name>.srt` subtitles labelling each segment with its source and text.
+// Returns the WAV path.
+pub fn render(segments: &[Segment], dir: &Path, name: &str, options: &SupercutOptions) -> Result<PathBuf> {
+    if segments.is_empty() {
+        return Err(ParasiteError::AudioProcessing("No matches to render".to_string()).into());
+    }
+
+    let mut parts = Vec::new();
+    for segment in segments {
+        parts.push(AudioBuffer::read_wav_range(&segment.source, segment.start, segment.end)?);
+    }
+
+    // Everything is mixed at the first segment's rate and the widest channel count
+    let sample_rate = parts[0].sample_rate;
+    let channels = parts.iter().map(|p| p.channels).max().unwrap_or(1);
+    let to_frames = |d: Duration| (d.as_secs_f64() * sample_rate as f64).round() as usize;
+    let gap = to_frames(options.gap);
+    let crossfade = to_frames(options.crossfade);
+
+    let mut montage = AudioBuffer { sample_rate, channels, samples: Vec::new() };
+    let mut cues = Vec::new();
+    let mut position = 0usize;
+    let mut previous_start = 0usize;
+
+    for (segment, part) in segments.iter().zip(parts) {
+        let mut part = part.resample(sample_rate).with_channels(channels);
+        let fade = crossfade.min(part.frames() / 2);
+        fade::apply(&mut part, fade, fade, FadeCurve::Linear);
+
+        // Overlap the previous segment's tail by the crossfade length
+        let start = position.max(previous_start);
+        mix_at(&mut montage, &part, start);
+
+        let end = start + part.frames();
+        cues.push(Cue {
+            start: frames_to_duration(start, sample_rate),
+            end: frames_to_duration(end, sample_rate),
+            label: label(segment),
+        });
+
+        previous_start = start;
+        position = (end + gap).saturating_sub(fade);
+    }
+
+    std::fs::create_dir_all(dir)?;
+    let wav_path = dir.join(format!("{}.wav", name));
+    montage.write_wav(&wav_path, 16, false)?;
+    std::fs::write(dir.join(format!("{}.vtt", name)), render_vtt(&cues))?;
+    std::fs::write(dir.join(format!("{}.srt", name)), render_srt(&cues))?;
+
+    Ok(wav_path)
+}
+
+fn label(segment: &Segment) -> String {
+    let source = segment.source.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    format!("[{} @ {:.2}s] {}", source, segment.start.as_secs_f64(), segment.text)
+}
+
+// Sum `part` into `montage` starting at frame `start`, growing it as needed
+fn mix_at(montage: &mut AudioBuffer, part: &AudioBuffer, start: usize) {
+    let channels = montage.channels as usize;
+    let needed = (start + part.frames()) * channels;
+    if montage.samples.len() < needed {
+        montage.samples.resize(needed, 0.0);
+    }
+
+    for (i, s) in part.samples.iter().enumerate() {
+        montage.samples[start * channels + i] += s;
+    }
+}
+
+fn frames_to_duration(frames: usize, sample_rate: u32) -> Duration {
+    Duration::from_secs_f64(frames as f64 / sample_rate as f64)
+}
+
+// Format as HH:MM:SS<sep>mmm (VTT uses '.', SRT uses ',')
+fn timestamp(time: Duration, separator: char) -> String {
+    let millis = time.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        (millis / 60_000) % 60,
+        (millis / 1000) % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+fn render_vtt(cues: &[Cue]) -> String {
+    let mut vtt = String::from("WEBVTT\n");
+    for cue in cues {
+        let _ = write!(
+            vtt,
+            "\n{} --> {}\n{}\n",
+            timestamp(cue.start, '.'),
+            timestamp(cue.end, '.'),
+            cue.label
+        );
+    }
+    vtt
+}
+
+fn render_srt(cues: &[Cue]) -> String {
+    let mut srt = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        let _ = write!(
+            srt,
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            timestamp(cue.start, ','),
+            timestamp(cue.end, ','),
+            cue.label
+        );
+    }
+    srt
+}
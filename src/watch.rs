@@ -0,0 +1,85 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::master;
+use crate::ParasiteError;
+
+// Changes are held until the directory has been quiet this long, so a file
+// still being written is only reloaded once
+const SETTLE: Duration = Duration::from_millis(500);
+
+// Extensions whose changes affect a recording: its transcript, audio
+// (including the master), license and master alignment
+const WATCHED: &[&str] = &["vtt", "wav", "license", "align"];
+
+// Watches the input directory for recordings being added, changed or removed
+pub struct SourceWatcher {
+    _watcher: RecommendedWatcher,
+    root: PathBuf,
+    events: Receiver<notify::Result<notify::Event>>,
+    pending: BTreeSet<PathBuf>, // VTT paths of recordings touched since the last batch
+    last_event: Option<Instant>,
+}
+
+impl SourceWatcher {
+    pub fn new(dir: &Path) -> Result<SourceWatcher> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)
+            .map_err(|e| ParasiteError::Config(format!("cannot watch {}: {}", dir.display(), e)))?;
+        watcher.watch(dir, RecursiveMode::Recursive)
+            .map_err(|e| ParasiteError::Config(format!("cannot watch {}: {}", dir.display(), e)))?;
+        Ok(SourceWatcher { _watcher: watcher, root: dir.to_path_buf(), events, pending: BTreeSet::new(), last_event: None })
+    }
+
+    // VTT paths of the recordings that changed, once things have settled.
+    // Never blocks; returns nothing while changes are still arriving.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        while let Ok(event) = self.events.try_recv() {
+            let Ok(event) = event else {
+                continue;
+            };
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            let recordings: Vec<PathBuf> = event.paths.iter().filter_map(|path| recording_for(&self.root, path)).collect();
+            if !recordings.is_empty() {
+                self.pending.extend(recordings);
+                self.last_event = Some(Instant::now());
+            }
+        }
+
+        match self.last_event {
+            Some(last) if last.elapsed() >= SETTLE => {
+                self.last_event = None;
+                std::mem::take(&mut self.pending).into_iter().collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+// The VTT of the recording a changed file belongs to. Files in hidden
+// folders (unfinished ingests) and partial writes don't count.
+fn recording_for(root: &Path, path: &Path) -> Option<PathBuf> {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    if relative.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.')) {
+        return None;
+    }
+    let extension = path.extension()?.to_string_lossy();
+    if !WATCHED.contains(&extension.as_ref()) {
+        return None;
+    }
+    let stem = path.file_stem()?.to_string_lossy();
+    if stem.ends_with(".partial") {
+        return None;
+    }
+
+    // `<name>.master.wav` and `<name>.master.align` belong to `<name>`
+    let name = stem.strip_suffix(&format!(".{}", master::MASTER_SUFFIX)).unwrap_or(&stem);
+    Some(path.with_file_name(format!("{}.vtt", name)))
+}
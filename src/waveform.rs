@@ -0,0 +1,72 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::audio::AudioBuffer;
+
+// Least room shown either side of a line, however short it is
+pub const MIN_MARGIN: Duration = Duration::from_millis(300);
+// Room either side as a share of the line's length
+pub const MARGIN_RATIO: f64 = 0.25;
+
+// Mono audio for the stretch of a recording on screen
+pub struct Waveform {
+    pub source: PathBuf,
+    pub start: Duration,
+    pub end: Duration,
+    sample_rate: u32,
+    samples: Vec<f32>,
+}
+
+impl Waveform {
+    pub fn load(source: &Path, start: Duration, end: Duration) -> Result<Waveform> {
+        let buffer = AudioBuffer::read_wav_range(source, start, end)?.to_mono();
+        Ok(Waveform {
+            source: source.to_path_buf(),
+            start,
+            end,
+            sample_rate: buffer.sample_rate,
+            samples: buffer.samples,
+        })
+    }
+
+    pub fn shows(&self, source: &Path, start: Duration, end: Duration) -> bool {
+        self.source == source && self.start == start && self.end == end
+    }
+
+    // Lowest and highest sample in each of `columns` equal slices of the
+    // view, for drawing one vertical stroke per column
+    pub fn peaks(&self, columns: usize) -> Vec<(f32, f32)> {
+        let span = (self.end - self.start).as_secs_f64();
+        (0..columns)
+            .map(|column| {
+                let time = |c: usize| span * c as f64 / columns as f64;
+                let first = ((time(column) * self.sample_rate as f64) as usize).min(self.samples.len());
+                let last = ((time(column + 1) * self.sample_rate as f64).ceil() as usize).clamp(first, self.samples.len());
+                self.samples[first..last]
+                    .iter()
+                    .fold(None, |range: Option<(f32, f32)>, &s| match range {
+                        Some((low, high)) => Some((low.min(s), high.max(s))),
+                        None => Some((s, s)),
+                    })
+                    .unwrap_or((0.0, 0.0))
+            })
+            .collect()
+    }
+
+    // Loudest sample in view, so quiet recordings still fill the pane
+    pub fn peak(&self) -> f32 {
+        self.samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()))
+    }
+}
+
+// The stretch shown for a line: everything in `ranges` plus a margin either
+// side, kept within the recording
+pub fn view_range(ranges: &[(Duration, Duration)], length: Option<Duration>) -> (Duration, Duration) {
+    let start = ranges.iter().map(|r| r.0).min().unwrap_or_default();
+    let end = ranges.iter().map(|r| r.1).max().unwrap_or_default().max(start);
+    let margin = (end - start).mul_f64(MARGIN_RATIO).max(MIN_MARGIN);
+    let view_end = end + margin;
+    (start.saturating_sub(margin), length.map_or(view_end, |length| view_end.min(length.max(end))))
+}